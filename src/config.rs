@@ -0,0 +1,117 @@
+//! The optional config file, `httpie/config.toml` in the user's config
+//! directory unless `--config` names another:
+//!
+//! ```toml
+//! # Sent only to this host.
+//! [hosts."api.internal.example.com".headers]
+//! Authorization = "Bearer s3cret"
+//!
+//! # Sent to every subdomain of example.com, but not example.com itself.
+//! [hosts."*.example.com".headers]
+//! X-Team = "web"
+//! ```
+
+use crate::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Rules keyed by host pattern.
+    pub hosts: BTreeMap<String, HostRule>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostRule {
+    pub headers: BTreeMap<String, String>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("httpie").join("config.toml"))
+}
+
+impl Config {
+    /// Reads the config at `path`, or at the default location. Only a
+    /// missing default file is not an error.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound && !required => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        };
+        toml::from_str(&data)
+            .map_err(|e| format!("Invalid config {}: {}", path.display(), e).into())
+    }
+
+    /// The headers configured for `host`, merged so that exact rules beat
+    /// wildcards and longer wildcards beat shorter ones.
+    pub fn headers_for(&self, host: &str) -> BTreeMap<&str, &str> {
+        let mut rules: Vec<(&String, &HostRule)> = self
+            .hosts
+            .iter()
+            .filter(|(pattern, _)| matches(pattern, host))
+            .collect();
+        rules.sort_by_key(|(pattern, _)| (!pattern.starts_with("*."), pattern.len()));
+        let mut headers = BTreeMap::new();
+        for (_, rule) in rules {
+            for (name, value) in &rule.headers {
+                headers.insert(name.as_str(), value.as_str());
+            }
+        }
+        headers
+    }
+}
+
+/// Matches a host exactly, ignoring case, or `*.domain` against any
+/// subdomain of `domain`.
+fn matches(pattern: &str, host: &str) -> bool {
+    let (pattern, host) = (pattern.to_ascii_lowercase(), host.to_ascii_lowercase());
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [hosts."api.example.com".headers]
+        Authorization = "Bearer a"
+
+        [hosts."*.example.com".headers]
+        Authorization = "Bearer wild"
+        X-Team = "web"
+    "#;
+
+    #[test]
+    fn test_headers_for() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        assert_eq!(
+            config.headers_for("API.example.com"),
+            BTreeMap::from([("Authorization", "Bearer a"), ("X-Team", "web")])
+        );
+        assert_eq!(
+            config.headers_for("www.example.com"),
+            BTreeMap::from([("Authorization", "Bearer wild"), ("X-Team", "web")])
+        );
+        assert!(config.headers_for("example.com").is_empty());
+        assert!(config.headers_for("evilexample.com").is_empty());
+        assert!(toml::from_str::<Config>("[hosts.a]\nheader = {}").is_err());
+    }
+}
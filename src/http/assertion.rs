@@ -0,0 +1,84 @@
+use crate::{json_path, Error, Result};
+use colored::*;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A `--assert path=expected` check against the JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    pub path: String,
+    /// Parsed as JSON when possible, otherwise taken as a string, so that
+    /// both `id=42` and `name=alice` read naturally.
+    pub expected: Value,
+}
+
+impl FromStr for Assertion {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (path, expected) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected <path>=<value>, got {}", s))?;
+        Ok(Self {
+            path: path.to_string(),
+            expected: serde_json::from_str(expected)
+                .unwrap_or_else(|_| Value::String(expected.to_string())),
+        })
+    }
+}
+
+impl Assertion {
+    /// Describes why the assertion fails on `body`, or `None` if it holds.
+    pub fn check(&self, body: Option<&Value>) -> Option<String> {
+        let Some(body) = body else {
+            return Some("body is not JSON".into());
+        };
+        match json_path::select(body, &self.path) {
+            Some(actual) if *actual == self.expected => None,
+            Some(actual) => Some(format!("expected {}, got {}", self.expected, actual)),
+            None => Some("not found".into()),
+        }
+    }
+}
+
+/// Runs every assertion against the body, printing a line for each, and
+/// fails if any of them does not hold.
+pub fn run(assertions: &[Assertion], body: &str) -> Result<()> {
+    let body: Option<Value> = serde_json::from_str(body).ok();
+    let mut failed = 0;
+    for assertion in assertions {
+        match assertion.check(body.as_ref()) {
+            None => meta!(
+                "{} {} = {}",
+                "✓".green(),
+                assertion.path,
+                assertion.expected
+            ),
+            Some(reason) => {
+                failed += 1;
+                meta!("{} {}: {}", "✗".red(), assertion.path, reason.red());
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} assertions failed", n, assertions.len()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check() {
+        let body = json!({"data": {"id": 42, "active": true, "name": "alice"}});
+        let check = |s: &str| s.parse::<Assertion>().unwrap().check(Some(&body));
+        assert_eq!(check("data.id=42"), None);
+        assert_eq!(check("data.active=true"), None);
+        assert_eq!(check("data.name=alice"), None);
+        assert_eq!(check("data.id=41"), Some("expected 41, got 42".into()));
+        assert_eq!(check("data.missing=1"), Some("not found".into()));
+        assert!("no-equals".parse::<Assertion>().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::sigv4::{self, Credentials, Scope};
+use crate::{netrc, Opts, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+
+/// Adds the credentials selected by the auth options to the request.
+/// Headers from the config come first so that SigV4 signs them.
+pub fn apply(req: &mut Request, opts: &Opts) -> Result<()> {
+    apply_config_headers(req, opts)?;
+    if opts.netrc || opts.netrc_required {
+        apply_netrc(req, opts)?;
+    }
+    if let Some(scope) = &opts.aws_sigv4 {
+        apply_sigv4(req, scope, opts)?;
+    }
+    Ok(())
+}
+
+/// Adds the headers the config file scopes to the request's host. They
+/// often carry credentials, so their values are marked sensitive.
+fn apply_config_headers(req: &mut Request, opts: &Opts) -> Result<()> {
+    let host = req.url().host_str().unwrap_or_default().to_string();
+    for (name, value) in opts.config.headers_for(&host) {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| format!("Invalid header name {:?} in config: {}", name, e))?;
+        let mut value = HeaderValue::try_from(value)
+            .map_err(|e| format!("Invalid value for {} in config: {}", name, e))?;
+        value.set_sensitive(true);
+        req.headers_mut().insert(name, value);
+    }
+    Ok(())
+}
+
+/// Uses the netrc login for the request's host as basic auth. A missing
+/// file or host only fails the request under `--netrc-required`.
+fn apply_netrc(req: &mut Request, opts: &Opts) -> Result<()> {
+    let host = req.url().host_str().unwrap_or_default().to_string();
+    let path = opts
+        .netrc_file
+        .clone()
+        .or_else(netrc::default_path)
+        .ok_or("Cannot locate a home directory for ~/.netrc")?;
+    let entry = match netrc::lookup(&path, &host) {
+        Ok(entry) => entry,
+        Err(e) if opts.netrc_required => return Err(e),
+        Err(_) => None,
+    };
+    let Some(entry) = entry else {
+        return match opts.netrc_required {
+            true => Err(format!("No netrc entry for {} in {}", host, path.display()).into()),
+            false => Ok(()),
+        };
+    };
+
+    let credentials = format!(
+        "{}:{}",
+        entry.login.unwrap_or_default(),
+        entry.password.unwrap_or_default()
+    );
+    let mut value = HeaderValue::try_from(format!("Basic {}", STANDARD.encode(credentials)))?;
+    value.set_sensitive(true);
+    req.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
+
+/// Signs the request with the AWS credentials from the flags or their
+/// environment variables, both of which must be present.
+fn apply_sigv4(req: &mut Request, scope: &Scope, opts: &Opts) -> Result<()> {
+    let access_key = opts.aws_access_key_id.clone().ok_or(
+        "--aws-sigv4 needs an access key: pass --aws-access-key-id or set AWS_ACCESS_KEY_ID",
+    )?;
+    let secret_key = opts.aws_secret_access_key.clone().ok_or(
+        "--aws-sigv4 needs a secret key: pass --aws-secret-access-key or set AWS_SECRET_ACCESS_KEY",
+    )?;
+    let creds = Credentials {
+        access_key,
+        secret_key,
+        session_token: opts.aws_session_token.clone(),
+    };
+    let time = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    sigv4::sign(req, scope, &creds, &time)
+}
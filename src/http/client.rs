@@ -0,0 +1,155 @@
+use super::redirect::{without_body, RedirectPolicy};
+use crate::Result;
+use reqwest::{redirect, Client, ClientBuilder, Method, Request, Response};
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A `reqwest::Client` that follows redirects through a [`RedirectPolicy`]
+/// and whose requests share one budget of concurrent connections, so that
+/// many in-flight requests cannot exhaust file descriptors.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    client: Client,
+    limiter: Option<Arc<Semaphore>>,
+    redirects: RedirectPolicy,
+}
+
+/// A response that still holds its connection slot. The slot is released
+/// when this is dropped, or when the permit from [`Limited::into_parts`] is,
+/// so a body being read still counts against `--max-connections`.
+#[derive(Debug)]
+pub struct Limited {
+    resp: Response,
+    method: Method,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Limited {
+    /// The method of the request that got this response, which a redirect
+    /// may have turned into a GET.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn into_parts(self) -> (Response, Option<OwnedSemaphorePermit>) {
+        (self.resp, self.permit)
+    }
+}
+
+impl Deref for Limited {
+    type Target = Response;
+
+    fn deref(&self) -> &Response {
+        &self.resp
+    }
+}
+
+impl HttpClient {
+    pub fn new(
+        builder: ClientBuilder,
+        max_connections: Option<NonZeroUsize>,
+        redirects: RedirectPolicy,
+    ) -> Result<Self> {
+        let client = builder.redirect(redirect::Policy::none()).build()?;
+        let limiter = max_connections.map(|n| Arc::new(Semaphore::new(n.get())));
+        Ok(Self {
+            client,
+            limiter,
+            redirects,
+        })
+    }
+
+    /// Sends the request, following redirects, with each hop waiting for a
+    /// free connection slot.
+    pub async fn send(&self, mut req: Request) -> Result<Limited> {
+        let mut hops = 0;
+        loop {
+            // Bodies that are streamed cannot be replayed, so their
+            // redirects are only followed where the body is dropped.
+            let (replay, has_body) = match req.try_clone() {
+                Some(replay) => (replay, true),
+                None => (without_body(&req), false),
+            };
+            let resp = self.execute(req).await?;
+            match self.redirects.next(replay, has_body, &resp, hops)? {
+                Some(next) => req = next,
+                None => return Ok(resp),
+            }
+            hops += 1;
+        }
+    }
+
+    /// Sends one request without following redirects.
+    pub async fn execute(&self, req: Request) -> Result<Limited> {
+        let permit = match &self.limiter {
+            Some(limiter) => Some(limiter.clone().acquire_owned().await?),
+            None => None,
+        };
+        let method = req.method().clone();
+        let resp = self.client.execute(req).await?;
+        Ok(Limited {
+            resp,
+            method,
+            permit,
+        })
+    }
+}
+
+impl Deref for HttpClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter_caps_permits() {
+        let new = |n| HttpClient::new(Client::builder(), n, RedirectPolicy::default()).unwrap();
+        let client = new(NonZeroUsize::new(2));
+        let limiter = client.limiter.as_ref().unwrap();
+        let _a = limiter.acquire().await.unwrap();
+        let _b = limiter.acquire().await.unwrap();
+        assert!(limiter.try_acquire().is_err());
+        assert!(new(None).limiter.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_response_holds_its_permit() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await;
+        });
+
+        let client = HttpClient::new(
+            Client::builder(),
+            NonZeroUsize::new(1),
+            RedirectPolicy::default(),
+        )
+        .unwrap();
+        let limiter = client.limiter.clone().unwrap();
+        let resp = client
+            .execute(client.get(&url).build().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(limiter.available_permits(), 0);
+        let (resp, permit) = resp.into_parts();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(limiter.available_permits(), 0);
+        drop(permit);
+        assert_eq!(limiter.available_permits(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+use serde_json::Value;
+
+/// Stands in for a collapsed container until the pretty-printed text is
+/// patched. Control characters are escaped by serde_json, so the quoted
+/// form cannot clash with a real string in the body.
+const OBJECT: &str = "\u{1}{...}";
+const ARRAY: &str = "\u{1}[...]";
+
+/// Pretty-prints `value` with objects and arrays nested more than `depth`
+/// levels down shown as `{...}` and `[...]`. The top-level container is
+/// level one, so depth 0 collapses everything.
+pub fn pretty_print(value: Value, depth: usize) -> serde_json::Result<String> {
+    let text = serde_json::to_string_pretty(&collapse(value, depth))?;
+    Ok(text
+        .replace(&serde_json::to_string(OBJECT)?, "{...}")
+        .replace(&serde_json::to_string(ARRAY)?, "[...]"))
+}
+
+fn collapse(value: Value, depth: usize) -> Value {
+    match value {
+        Value::Object(map) if depth == 0 && !map.is_empty() => Value::String(OBJECT.into()),
+        Value::Array(items) if depth == 0 && !items.is_empty() => Value::String(ARRAY.into()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, collapse(v, depth.saturating_sub(1))))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| collapse(v, depth.saturating_sub(1)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pretty_print() {
+        let value = json!({"a": {"b": {"c": 1}}, "list": [[1], 2], "empty": {}, "s": "{...}"});
+        assert_eq!(
+            pretty_print(value.clone(), 2).unwrap(),
+            serde_json::to_string_pretty(
+                &json!({"a": {"b": "B"}, "list": ["L", 2], "empty": {}, "s": "{...}"})
+            )
+            .unwrap()
+            .replace("\"B\"", "{...}")
+            .replace("\"L\"", "[...]")
+        );
+        assert_eq!(pretty_print(value, 0).unwrap(), "{...}");
+        assert_eq!(pretty_print(json!([1, 2]), 1).unwrap(), "[\n  1,\n  2\n]");
+        let unsorted = serde_json::from_str(r#"{"z": {"y": 1, "x": 2}, "a": [3]}"#).unwrap();
+        assert_eq!(
+            pretty_print(unsorted, 1).unwrap(),
+            "{\n  \"z\": {...},\n  \"a\": [...]\n}"
+        );
+        let unsorted = serde_json::from_str(r#"{"z": {"y": 1, "x": 2}}"#).unwrap();
+        assert_eq!(
+            pretty_print(unsorted, 2).unwrap(),
+            "{\n  \"z\": {\n    \"y\": 1,\n    \"x\": 2\n  }\n}"
+        );
+    }
+}
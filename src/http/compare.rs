@@ -0,0 +1,125 @@
+use super::client::HttpClient;
+use super::{paint_status, parse_secs};
+use crate::{Error, Result};
+use clap::Args;
+use colored::*;
+use reqwest::Url;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+#[derive(Args, Debug)]
+pub struct Compare {
+    /// Base URLs to compare, optionally named: prod=https://api.example.com
+    #[arg(required = true, num_args = 1..)]
+    pub targets: Vec<Target>,
+    /// The path fetched from every base URL.
+    #[arg(long, default_value = "/")]
+    pub path: String,
+    /// Seconds to wait for each environment.
+    #[arg(long, value_name = "SECS", default_value = "10", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+/// One environment: a label and the base URL the path is appended to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub base: Url,
+}
+
+impl FromStr for Target {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        // A name never contains "//", so a bare URL is not mistaken for one.
+        let (name, url) = match s.split_once('=') {
+            Some((name, url)) if !name.contains("//") => (Some(name), url),
+            _ => (None, s),
+        };
+        let base: Url = url.parse()?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => base.host_str().unwrap_or(url).to_string(),
+        };
+        Ok(Self { name, base })
+    }
+}
+
+impl Target {
+    /// Appends `path` to the base URL, keeping any path the base already has.
+    fn url(&self, path: &str) -> Url {
+        let mut url = self.base.clone();
+        let joined = format!(
+            "{}/{}",
+            url.path().trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+        url.set_path(&joined);
+        url
+    }
+}
+
+pub async fn compare(client: &HttpClient, args: &Compare) -> Result<()> {
+    let mut fetches = JoinSet::new();
+    for target in &args.targets {
+        let client = client.clone();
+        let url = target.url(&args.path);
+        let req = client.get(url.clone()).timeout(args.timeout).build()?;
+        let name = target.name.clone();
+        fetches.spawn(async move {
+            let start = Instant::now();
+            let status = client.send(req).await.map(|resp| resp.status());
+            (name, url, status, start.elapsed())
+        });
+    }
+
+    let mut results = Vec::with_capacity(args.targets.len());
+    while let Some(joined) = fetches.join_next().await {
+        results.push(joined?);
+    }
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let name_width = results.iter().map(|r| r.0.len()).max().unwrap_or_default();
+    for (name, url, status, elapsed) in &results {
+        let status = match status {
+            Ok(s) => paint_status(*s),
+            Err(e) => e.to_string().red(),
+        };
+        println!(
+            "{:name_width$}  {:>8.1} ms  {}  {}",
+            name.bold(),
+            elapsed.as_secs_f64() * 1000.0,
+            status,
+            url.as_str().dimmed()
+        );
+    }
+    let outcomes: HashSet<_> = results.iter().map(|r| r.2.as_ref().ok()).collect();
+    if outcomes.len() > 1 {
+        println!("\n{}", "Environments disagree".yellow());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        let t: Target = "prod=https://api.example.com/v1".parse().unwrap();
+        assert_eq!(t.name, "prod");
+        assert_eq!(
+            t.url("/health").as_str(),
+            "https://api.example.com/v1/health"
+        );
+
+        let t: Target = "https://eu.example.com/?a=b".parse().unwrap();
+        assert_eq!(t.name, "eu.example.com");
+        assert_eq!(
+            t.url("health").as_str(),
+            "https://eu.example.com/health?a=b"
+        );
+        assert!("prod=not a url".parse::<Target>().is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Response decompression. reqwest's own decoding is left off because it
+//! drops `Content-Encoding` and the compressed size, which
+//! `--show-compression` reports. Bodies are kept as received and decoded
+//! only where they are read, so downloads and `--tee` get the wire bytes.
+
+use crate::Result;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Sent as `Accept-Encoding` under `--show-compression`.
+pub const ACCEPT: &str = "gzip, deflate, br";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compression {
+    /// The `Content-Encoding` as sent, e.g. `gzip`.
+    pub encoding: String,
+    /// Bytes on the wire, before decoding.
+    pub compressed: usize,
+}
+
+/// Decodes `body` by its `Content-Encoding`, undoing the codings in reverse
+/// order. Bodies in an unknown coding are returned untouched without a
+/// `Compression`.
+pub fn decode<'a>(
+    headers: &HeaderMap,
+    body: &'a [u8],
+) -> Result<(Cow<'a, [u8]>, Option<Compression>)> {
+    let Some(encoding) = headers.get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()) else {
+        return Ok((Cow::Borrowed(body), None));
+    };
+    let codings: Vec<_> = encoding
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .filter(|c| !c.is_empty() && c != "identity")
+        .collect();
+    if codings.is_empty() || !codings.iter().all(|c| is_known(c)) {
+        return Ok((Cow::Borrowed(body), None));
+    }
+
+    let mut decoded = body.to_vec();
+    for coding in codings.iter().rev() {
+        decoded = decode_one(coding, &decoded)
+            .map_err(|e| format!("Failed to decode the {} body: {}", coding, e))?;
+    }
+    let compression = Compression {
+        encoding: encoding.to_string(),
+        compressed: body.len(),
+    };
+    Ok((Cow::Owned(decoded), Some(compression)))
+}
+
+fn is_known(coding: &str) -> bool {
+    matches!(coding, "gzip" | "x-gzip" | "deflate" | "br")
+}
+
+fn decode_one(coding: &str, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match coding {
+        "gzip" | "x-gzip" => GzDecoder::new(data).read_to_end(&mut out)?,
+        // "deflate" should be zlib-wrapped, but some servers send it raw.
+        "deflate" => match ZlibDecoder::new(data).read_to_end(&mut out) {
+            Ok(n) => n,
+            Err(_) => {
+                out.clear();
+                DeflateDecoder::new(data).read_to_end(&mut out)?
+            }
+        },
+        _ => brotli_decompressor::Decompressor::new(data, 4096).read_to_end(&mut out)?,
+    };
+    Ok(out)
+}
+
+/// A one-line summary such as `gzip, 1204 → 5410 bytes (4.5x smaller)`.
+pub fn describe(compression: Option<&Compression>, decoded: usize) -> String {
+    match compression {
+        Some(c) => format!(
+            "{}, {} → {} bytes ({:.1}x smaller)",
+            c.encoding,
+            c.compressed,
+            decoded,
+            decoded as f64 / c.compressed.max(1) as f64
+        ),
+        None => format!("not compressed, {} bytes", decoded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use reqwest::header::HeaderValue;
+    use std::io::Write;
+
+    #[test]
+    fn test_decode_gzip() {
+        let text = "hello ".repeat(100);
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        let (body, compression) = decode(&headers, &gzipped).unwrap();
+        assert_eq!(body, text.as_bytes());
+        let compression = compression.unwrap();
+        assert_eq!(compression.compressed, gzipped.len());
+        assert!(describe(Some(&compression), body.len()).starts_with("gzip, "));
+
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("zstd"));
+        assert_eq!(
+            decode(&headers, b"raw").unwrap(),
+            (Cow::Borrowed(&b"raw"[..]), None)
+        );
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        assert!(decode(&headers, b"not gzip").is_err());
+    }
+}
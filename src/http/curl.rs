@@ -0,0 +1,148 @@
+use crate::Result;
+use reqwest::{Method, Request};
+
+/// Builds a curl command that sends the same request. Like `--print-wire`,
+/// it only knows what is on the `Request`, so the client's default headers
+/// are left for curl to supply. Streamed bodies, such as multipart
+/// uploads, cannot be read back and are refused. Header values marked
+/// sensitive, the credentials from netrc, the config file and SigV4, are
+/// shown as `<redacted>` unless `unredacted` is set, since the command is
+/// likely to be pasted somewhere.
+pub fn render(req: &Request, unredacted: bool) -> Result<String> {
+    let mut args = vec!["curl".to_string()];
+    match (req.method(), req.body()) {
+        (&Method::GET, None) => {}
+        (&Method::HEAD, None) => args.push("--head".into()),
+        (method, _) => args.extend(["-X".into(), method.to_string()]),
+    }
+    for (name, value) in req.headers() {
+        let value = match value.is_sensitive() && !unredacted {
+            true => "<redacted>".into(),
+            false => String::from_utf8_lossy(value.as_bytes()),
+        };
+        let header = format!("{}: {}", name, value);
+        args.extend(["-H".into(), quote(&header)]);
+    }
+    if let Some(body) = req.body() {
+        let bytes = body
+            .as_bytes()
+            .ok_or("A streamed body, such as a multipart upload, cannot be exported to curl")?;
+        args.extend([
+            "--data-binary".into(),
+            quote(&String::from_utf8_lossy(bytes)),
+        ]);
+    }
+    args.push(quote(req.url().as_str()));
+    Ok(args.join(" "))
+}
+
+/// Single-quotes a word for POSIX shells, leaving plain words bare.
+fn quote(s: &str) -> String {
+    let plain = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    match plain {
+        true => s.to_string(),
+        false => format!("'{}'", s.replace('\'', r"'\''")),
+    }
+}
+
+/// Set for the background copy of httpie that keeps a copied command on
+/// the clipboard.
+const HOLDER: &str = "HTTPIE_CLIPBOARD_HOLDER";
+
+/// Puts the command on the system clipboard. On X11 and Wayland the text
+/// is served by the process that set it and is gone once that process
+/// exits, so a copy of httpie is left in the background to serve it until
+/// something else is copied.
+pub fn copy(command: &str) -> Result<()> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Cannot open the clipboard: {}", e))?;
+    clipboard
+        .set_text(command)
+        .map_err(|e| format!("Cannot copy to the clipboard: {}", e))?;
+    #[cfg(target_os = "linux")]
+    spawn_holder(command)
+        .map_err(|e| format!("Cannot keep the command on the clipboard: {}", e))?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_holder(command: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    // The holder outlives this process, which is why it is not waited on.
+    #[allow(clippy::zombie_processes)]
+    let mut holder = Command::new(std::env::current_exe()?)
+        .env(HOLDER, "1")
+        .current_dir("/")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    holder
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(command.as_bytes())
+}
+
+/// When this process is the holder started by `copy`, serves the command
+/// read from stdin until the clipboard is taken over and returns true.
+pub fn hold() -> bool {
+    #[cfg(target_os = "linux")]
+    if std::env::var_os(HOLDER).is_some() {
+        use arboard::SetExtLinux;
+        use std::io::Read;
+        let mut command = String::new();
+        if std::io::stdin().read_to_string(&mut command).is_ok() {
+            if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                let _ = clipboard.set().wait().text(command);
+            }
+        }
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let client = reqwest::Client::new();
+        let req = client
+            .post("http://localhost:8080/items?page=2")
+            .header("x-note", "it's")
+            .body(r#"{"a":1}"#)
+            .build()
+            .unwrap();
+        assert_eq!(
+            render(&req, false).unwrap(),
+            r#"curl -X POST -H 'x-note: it'\''s' --data-binary '{"a":1}' 'http://localhost:8080/items?page=2'"#
+        );
+        let get = client.get("http://localhost/").build().unwrap();
+        assert_eq!(render(&get, false).unwrap(), "curl http://localhost/");
+    }
+
+    #[test]
+    fn test_render_redacts_sensitive_headers() {
+        let mut secret = reqwest::header::HeaderValue::from_static("Basic dTpw");
+        secret.set_sensitive(true);
+        let req = reqwest::Client::new()
+            .get("http://localhost/")
+            .header("authorization", secret)
+            .header("x-team", "web")
+            .build()
+            .unwrap();
+        assert_eq!(
+            render(&req, false).unwrap(),
+            "curl -H 'authorization: <redacted>' -H 'x-team: web' http://localhost/"
+        );
+        assert_eq!(
+            render(&req, true).unwrap(),
+            "curl -H 'authorization: Basic dTpw' -H 'x-team: web' http://localhost/"
+        );
+    }
+}
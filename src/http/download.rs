@@ -0,0 +1,187 @@
+use super::client::HttpClient;
+use super::incoming::{Incoming, Received};
+use super::{get_content_type, print_headers, print_status, Captured};
+use crate::{Opts, Result};
+use colored::*;
+use mime::Mime;
+use reqwest::{Request, Url};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The name used when the URL has no usable last path segment.
+const DEFAULT_NAME: &str = "index";
+
+/// A response whose body went to a file rather than into memory.
+pub struct Saved {
+    pub received: Received,
+    pub path: PathBuf,
+    /// The SHA-256 of the saved bytes, when `--sha256` asked for it.
+    pub digest: Option<String>,
+}
+
+/// Sends the request and streams the body to `--output`, or to a file
+/// named after the URL, hashing each chunk as it is written so that large
+/// downloads are never held in memory. Derived names never overwrite an
+/// existing file. The bytes are saved as received.
+pub async fn fetch(client: &HttpClient, req: Request, opts: &Opts) -> Result<Saved> {
+    let incoming = Incoming::send(client, req).await?;
+    let path = match opts.output.as_deref() {
+        Some(path) => path.to_path_buf(),
+        None => file_name(
+            incoming.url(),
+            get_content_type(incoming.headers()).as_ref(),
+        )
+        .into(),
+    };
+    let mut file = create(&path, opts.output.is_some())?;
+    let mut hasher = opts.sha256.is_some().then(Sha256::new);
+    let received = incoming
+        .read(|chunk| {
+            file.write_all(chunk)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            if let Some(hasher) = &mut hasher {
+                hasher.update(chunk);
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(Saved {
+        received,
+        path,
+        digest: hasher.map(|h| hex::encode(h.finalize())),
+    })
+}
+
+fn create(path: &Path, overwrite: bool) -> Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(overwrite)
+        .create_new(!overwrite)
+        .truncate(overwrite)
+        .open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e).into())
+}
+
+/// Prints the response head and where the body went, then checks the
+/// digest against `--sha256`.
+pub fn report(saved: &Saved, opts: &Opts) -> Result<()> {
+    let received = &saved.received;
+    print_status(received.version, received.status)?;
+    print_headers(&received.headers, opts)?;
+    meta!(
+        "{} {} bytes to {}",
+        "Saved".green(),
+        received.bytes,
+        saved.path.display()
+    );
+    match (&saved.digest, &opts.sha256) {
+        (Some(actual), Some(expected)) => verify(actual, expected.as_deref()),
+        _ => Ok(()),
+    }
+}
+
+/// Parses an expected `--sha256` digest: 64 hex digits, optionally prefixed
+/// with `sha256:` as `--fingerprint` prints them.
+pub fn parse_sha256(s: &str) -> Result<String> {
+    let digest = s.trim();
+    let digest = digest.strip_prefix("sha256:").unwrap_or(digest);
+    match digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Ok(digest.to_ascii_lowercase()),
+        false => Err(format!("{:?} is not a SHA-256 digest of 64 hex digits", s).into()),
+    }
+}
+
+/// Prints the SHA-256 of the saved bytes and, when a digest is expected,
+/// fails unless they match. The file is kept either way so that it can be
+/// inspected.
+fn verify(actual: &str, expected: Option<&str>) -> Result<()> {
+    match expected {
+        None => meta!("{} {}", "SHA-256:".bold(), actual),
+        Some(expected) if expected == actual => {
+            meta!("{} {} {}", "SHA-256:".bold(), actual, "(verified)".green())
+        }
+        Some(expected) => {
+            return Err(format!("SHA-256 mismatch: expected {}, got {}", expected, actual).into())
+        }
+    }
+    Ok(())
+}
+
+/// Copies the raw body to `path`, leaving the printed output untouched.
+pub fn tee(resp: &Captured, path: &Path) -> Result<()> {
+    fs::write(path, &resp.body)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Derives a file name from the URL's last path segment, adding an
+/// extension inferred from the content type when the segment has none.
+fn file_name(url: &Url, mime: Option<&Mime>) -> String {
+    let base = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_NAME);
+    if Path::new(base).extension().is_some() {
+        return base.into();
+    }
+    match mime.and_then(extension) {
+        Some(ext) => format!("{}.{}", base, ext),
+        None => base.into(),
+    }
+}
+
+/// Reverse-looks-up an extension for the content type. `mime_guess` lists
+/// extensions alphabetically, so prefer the one named after the subtype
+/// (`html` over `htm`) and `txt` for plain text.
+fn extension(m: &Mime) -> Option<&'static str> {
+    let exts = mime_guess::get_mime_extensions(m)?;
+    let subtype = m.subtype().as_str();
+    exts.iter()
+        .find(|ext| **ext == subtype || **ext == "txt")
+        .or_else(|| exts.first())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let name = |url: &str, mime: Option<&str>| {
+            let url = url.parse().unwrap();
+            let mime: Option<Mime> = mime.map(|m| m.parse().unwrap());
+            file_name(&url, mime.as_ref())
+        };
+        assert_eq!(
+            name("https://x.io/report", Some("application/json")),
+            "report.json"
+        );
+        assert_eq!(name("https://x.io/logo", Some("image/png")), "logo.png");
+        assert_eq!(
+            name("https://x.io/", Some("text/html; charset=utf-8")),
+            "index.html"
+        );
+        assert_eq!(name("https://x.io/notes", Some("text/plain")), "notes.txt");
+        assert_eq!(
+            name("https://x.io/a.tar.gz", Some("application/json")),
+            "a.tar.gz"
+        );
+        assert_eq!(name("https://x.io/blob", None), "blob");
+    }
+
+    #[test]
+    fn test_verify() {
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(parse_sha256(&digest.to_uppercase()).unwrap(), digest);
+        assert_eq!(parse_sha256(&format!("sha256:{}", digest)).unwrap(), digest);
+        assert!(parse_sha256("abc").is_err());
+        let hello = hex::encode(Sha256::digest(b"hello"));
+        assert!(verify(&hello, Some(digest)).is_ok());
+        let other = hex::encode(Sha256::digest(b"hello!"));
+        assert!(verify(&other, Some(digest)).is_err());
+    }
+}
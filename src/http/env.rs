@@ -0,0 +1,64 @@
+//! Expansion of `$NAME` and `${NAME}` references for `--expand-env-in-url`.
+
+use crate::Result;
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// `${NAME}` or a bare `$NAME`. A `$` that starts neither is left as is,
+/// since it is valid in URL paths.
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)").unwrap()
+});
+
+/// Replaces references with the environment's values. An unset variable
+/// is an error unless `allow_unset`, when it becomes empty.
+pub fn expand(s: &str, allow_unset: bool) -> Result<String> {
+    expand_with(s, allow_unset, |name| std::env::var(name).ok())
+}
+
+fn expand_with(
+    s: &str,
+    allow_unset: bool,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut unset = Vec::new();
+    let expanded = REFERENCE.replace_all(s, |caps: &Captures| {
+        let name = caps.get(1).or_else(|| caps.get(2)).unwrap().as_str();
+        lookup(name).unwrap_or_else(|| {
+            unset.push(format!("${}", name));
+            String::new()
+        })
+    });
+    if !unset.is_empty() && !allow_unset {
+        return Err(format!(
+            "{} not set; export it or pass --allow-unset-env to expand it to nothing",
+            unset.join(", ")
+        )
+        .into());
+    }
+    Ok(expanded.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_with() {
+        let lookup = |name: &str| (name == "API_HOST").then(|| "api.example.com".to_string());
+        assert_eq!(
+            expand_with("https://$API_HOST/v1/${API_HOST}x", false, lookup).unwrap(),
+            "https://api.example.com/v1/api.example.comx"
+        );
+        assert_eq!(
+            expand_with("https://h/$1/a$", false, lookup).unwrap(),
+            "https://h/$1/a$"
+        );
+        let err = expand_with("https://$NOPE/", false, lookup).unwrap_err();
+        assert!(err.to_string().starts_with("$NOPE not set"));
+        assert_eq!(
+            expand_with("https://h/$NOPE", true, lookup).unwrap(),
+            "https://h/"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use super::post::BodySource;
+use super::{resolve_url, Method};
+use crate::Opts;
+use reqwest::Url;
+
+/// Describes in plain English what the parsed command line will do, so long
+/// invocations can be checked before they hit the network.
+pub fn explain(opts: &Opts) -> String {
+    let (verb, url, params) = match &opts.method {
+        Method::Get(args) => ("GET", &args.url, &args.path_params),
+        Method::Post(args) => ("POST", &args.url, &args.path_params),
+        Method::Probe(args) => {
+            return format!(
+                "Probing {} over http and https on the default ports and {}, \
+                 allowing {:?} per attempt.",
+                args.host,
+                plural(args.ports.len(), "extra port"),
+                args.timeout
+            );
+        }
+        Method::Compare(args) => {
+            let names: Vec<_> = args.targets.iter().map(|t| t.name.as_str()).collect();
+            return format!(
+                "Fetching {} from {} ({}) and comparing their statuses.",
+                args.path,
+                plural(names.len(), "environment"),
+                names.join(", ")
+            );
+        }
+    };
+    // A template that fails to resolve is shown as given; sending reports why.
+    let url = resolve_url(url, params, opts).unwrap_or_else(|_| url.clone());
+    let mut clauses = vec![format!("Sending a {} to {}", verb, url)];
+
+    if let Method::Post(args) = &opts.method {
+        let (kind, unit) = match args.multipart {
+            true => ("multipart", "part"),
+            false => ("JSON", "field"),
+        };
+        let fields = (&args.json_body, &args.body_yaml, args.body.len());
+        clauses.push(match (&args.body_source, fields) {
+            (Some(BodySource::Last), _) => "with the last response as the body".into(),
+            (Some(BodySource::File(path)), _) => {
+                format!("with the body read from {}", path.display())
+            }
+            (None, (Some(_), _, _)) => "with the JSON body given".into(),
+            (None, (None, Some(path), _)) => {
+                format!("with a JSON body converted from {}", path.display())
+            }
+            (None, (None, None, 0)) => format!("with an empty {} body", kind),
+            (None, (None, None, n)) => format!("with a {} body of {}", kind, plural(n, unit)),
+        });
+    }
+    let host = Url::parse(&url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from));
+    let configured = opts.config.headers_for(host.as_deref().unwrap_or_default());
+    if !configured.is_empty() {
+        let names: Vec<_> = configured.keys().copied().collect();
+        clauses.push(format!(
+            "adding {} from the config ({})",
+            plural(names.len(), "header"),
+            names.join(", ")
+        ));
+    }
+    if opts.netrc || opts.netrc_required {
+        let file = match &opts.netrc_file {
+            Some(path) => path.display().to_string(),
+            None => "~/.netrc".into(),
+        };
+        clauses.push(format!("using basic auth from {}", file));
+    }
+    if let Some(scope) = &opts.aws_sigv4 {
+        clauses.push(format!(
+            "signed with AWS SigV4 for {} in {}",
+            scope.service, scope.region
+        ));
+    }
+    match &opts.request_id {
+        Some(Some(id)) => clauses.push(format!("tagged with X-Request-Id {}", id)),
+        Some(None) => clauses.push("tagged with a generated X-Request-Id".into()),
+        None => {}
+    }
+    if opts.warmup {
+        clauses.push("after warming up the connection with a HEAD request".into());
+    }
+    if let Some(n) = opts.max_redirects {
+        clauses.push(format!("following up to {}", plural(n, "redirect")));
+    }
+    if opts.keep_method_on_redirect {
+        clauses.push(format!("replaying the {} on every redirect", verb));
+    }
+    if opts.allow_cross_origin_auth {
+        clauses.push("keeping credentials on redirects to other hosts".into());
+    }
+    if opts.no_http_downgrade {
+        clauses.push("refusing redirects from https to http".into());
+    }
+    if opts.trace {
+        clauses.push("printing each redirect hop".into());
+    }
+    if let Some(ip) = opts.bind {
+        clauses.push(format!("sent from local address {}", ip));
+    }
+    if let Some(n) = opts.max_connections {
+        clauses.push(format!(
+            "keeping at most {} in flight",
+            plural(n.get(), "request")
+        ));
+    }
+
+    let until = match (
+        &opts.repeat_until_body_contains,
+        &opts.repeat_until_body_matches,
+    ) {
+        (Some(s), _) => Some(format!("the body contains {:?}", s)),
+        (_, Some(re)) => Some(format!("the body matches /{}/", re)),
+        _ => None,
+    };
+    if let Some(until) = until {
+        clauses.push(format!(
+            "re-sending every {:?} until {} (at most {})",
+            opts.interval,
+            until,
+            plural(opts.max_attempts as usize, "attempt")
+        ));
+    }
+    if let Some(path) = &opts.tee {
+        clauses.push(format!("copying the body to {}", path.display()));
+    }
+    if let Some(path) = &opts.metrics_file {
+        clauses.push(format!("recording metrics in {}", path.display()));
+    }
+    if opts.download {
+        clauses.push(match &opts.output {
+            Some(path) => format!("saving the body to {}", path.display()),
+            None => "saving the body to a file named after the URL".into(),
+        });
+    }
+    match &opts.sha256 {
+        Some(Some(digest)) => clauses.push(format!("checking its SHA-256 is {}", digest)),
+        Some(None) => clauses.push("printing its SHA-256".into()),
+        None => {}
+    }
+
+    format!("{}.", clauses.join(", "))
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_explain() {
+        let opts = Opts::parse_from(["httpie", "get", "https://x.io/"]);
+        assert_eq!(explain(&opts), "Sending a GET to https://x.io/.");
+
+        let opts = Opts::parse_from([
+            "httpie",
+            "post",
+            "https://x.io/jobs",
+            "a=1",
+            "b=2",
+            "--request-id",
+            "--repeat-until-body-contains",
+            "done",
+            "--max-attempts",
+            "3",
+        ]);
+        assert_eq!(
+            explain(&opts),
+            "Sending a POST to https://x.io/jobs, with a JSON body of 2 fields, \
+             tagged with a generated X-Request-Id, re-sending every 1s until \
+             the body contains \"done\" (at most 3 attempts)."
+        );
+    }
+}
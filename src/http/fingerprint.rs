@@ -0,0 +1,55 @@
+use reqwest::Request;
+use sha2::{Digest, Sha256};
+
+/// Hashes the method, URL, headers sorted by name and value, and body, so
+/// the same request gives the same fingerprint on every run. Streamed
+/// bodies cannot be read and contribute only a marker; multipart bodies
+/// also get a random boundary, which makes their fingerprints vary anyway.
+pub fn fingerprint(req: &Request) -> String {
+    let mut headers: Vec<(&str, &[u8])> = req
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()))
+        .collect();
+    headers.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{} {}\n", req.method(), req.url()));
+    for (name, value) in headers {
+        hasher.update(name);
+        hasher.update(b":");
+        hasher.update(value);
+        hasher.update(b"\n");
+    }
+    hasher.update(b"\n");
+    match req.body().map(|b| b.as_bytes()) {
+        Some(Some(bytes)) => hasher.update(bytes),
+        Some(None) => hasher.update(b"<streamed body>"),
+        None => {}
+    }
+    format!("sha256:{}", hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_header_order() {
+        let client = reqwest::Client::new();
+        let build = |first: (&str, &str), second: (&str, &str)| {
+            client
+                .post("http://localhost/a?b=1")
+                .header(first.0, first.1)
+                .header(second.0, second.1)
+                .body("{}")
+                .build()
+                .unwrap()
+        };
+        let a = fingerprint(&build(("x-a", "1"), ("x-b", "2")));
+        let b = fingerprint(&build(("x-b", "2"), ("x-a", "1")));
+        assert_eq!(a, b);
+        assert!(a.starts_with("sha256:"));
+        assert_ne!(a, fingerprint(&build(("x-a", "1"), ("x-b", "3"))));
+    }
+}
@@ -0,0 +1,264 @@
+//! Snapshot testing of response bodies for `--expect-body-file`.
+
+use crate::Result;
+use colored::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Lines of unchanged context kept around each change in a diff.
+const CONTEXT: usize = 3;
+
+/// Compares `body` with the fixture at `path`, or overwrites the fixture
+/// with it when `update`. A mismatch prints a diff from the fixture to the
+/// response and fails.
+pub fn check(body: &str, path: &Path, update: bool) -> Result<()> {
+    let actual = normalize(body);
+    if update {
+        fs::write(path, format!("{}\n", actual))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        meta!("{} {}", "Updated fixture".green(), path.display());
+        return Ok(());
+    }
+    let expected = fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Failed to read {}: {}; pass --update-fixture to create it",
+            path.display(),
+            e
+        )
+    })?;
+    let expected = normalize(&expected);
+    if expected == actual {
+        return Ok(());
+    }
+    meta!("{}", format!("--- {}", path.display()).red());
+    meta!("{}", "+++ response".green());
+    for line in diff(&expected, &actual) {
+        meta!("{}", line);
+    }
+    Err(format!("Response body differs from {}", path.display()).into())
+}
+
+/// JSON is pretty-printed with its keys sorted, so that neither formatting
+/// nor key order counts as a change. Other text only loses trailing
+/// whitespace.
+fn normalize(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            value.sort_all_objects();
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        }
+        Err(_) => body.trim_end().to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// A line diff, colored and cut down to the changes and their context.
+fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    let ops = edits(&old, &new);
+    let mut near_change = vec![false; ops.len()];
+    for (i, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Same)
+    {
+        let end = (i + CONTEXT + 1).min(ops.len());
+        near_change[i.saturating_sub(CONTEXT)..end].fill(true);
+    }
+    let mut out = Vec::new();
+    let mut skipped = false;
+    for ((op, line), near) in ops.iter().zip(near_change) {
+        if !near {
+            skipped = true;
+            continue;
+        }
+        if std::mem::take(&mut skipped) {
+            out.push("...".dimmed().to_string());
+        }
+        out.push(match op {
+            Op::Same => format!("  {}", line),
+            Op::Removed => format!("- {}", line).red().to_string(),
+            Op::Added => format!("+ {}", line).green().to_string(),
+        });
+    }
+    out
+}
+
+/// A shortest edit script from `old` to `new`, found with the linear-space
+/// variant of Myers' algorithm, so memory grows with the line counts rather
+/// than their product.
+fn edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    let mut v = Diagonals::new(old.len() + new.len());
+    conquer(old, new, &mut v, &mut ops);
+    ops
+}
+
+/// Furthest-reaching x for each diagonal `k = x - y`, forward and backward.
+struct Diagonals {
+    offset: isize,
+    forward: Vec<usize>,
+    backward: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(max: usize) -> Self {
+        let len = 2 * (max / 2 + 2) + 1;
+        Self {
+            offset: (len / 2) as isize,
+            forward: vec![0; len],
+            backward: vec![0; len],
+        }
+    }
+
+    fn at(&self, k: isize) -> usize {
+        (k + self.offset) as usize
+    }
+}
+
+fn conquer<'a>(old: &[&'a str], new: &[&'a str], v: &mut Diagonals, ops: &mut Vec<(Op, &'a str)>) {
+    let prefix = common_prefix(old, new);
+    ops.extend(old[..prefix].iter().map(|line| (Op::Same, *line)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let suffix = common_suffix(old, new);
+    let (old, new, tail) = (
+        &old[..old.len() - suffix],
+        &new[..new.len() - suffix],
+        &old[old.len() - suffix..],
+    );
+
+    match middle_snake(old, new, v) {
+        _ if old.is_empty() => ops.extend(new.iter().map(|line| (Op::Added, *line))),
+        _ if new.is_empty() => ops.extend(old.iter().map(|line| (Op::Removed, *line))),
+        Some((x, y)) => {
+            conquer(&old[..x], &new[..y], v, ops);
+            conquer(&old[x..], &new[y..], v, ops);
+        }
+        None => {
+            ops.extend(old.iter().map(|line| (Op::Removed, *line)));
+            ops.extend(new.iter().map(|line| (Op::Added, *line)));
+        }
+    }
+    ops.extend(tail.iter().map(|line| (Op::Same, *line)));
+}
+
+/// Finds where an optimal path crosses the middle by searching from both
+/// ends at once. The returned point splits the problem in two.
+fn middle_snake(old: &[&str], new: &[&str], v: &mut Diagonals) -> Option<(usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    if n == 0 || m == 0 {
+        return None;
+    }
+    let delta = n as isize - m as isize;
+    let odd = delta % 2 != 0;
+    let (f1, b1) = (v.at(1), v.at(1));
+    v.forward[f1] = 0;
+    v.backward[b1] = 0;
+    for d in 0..=(n + m).div_ceil(2) as isize {
+        for k in (-d..=d).step_by(2) {
+            let (down, up) = (v.at(k - 1), v.at(k + 1));
+            let mut x = match k == -d || (k != d && v.forward[down] < v.forward[up]) {
+                true => v.forward[up],
+                false => v.forward[down] + 1,
+            };
+            let start = (x, (x as isize - k) as usize);
+            let y = start.1;
+            if x < n && y < m {
+                x += common_prefix(&old[x..], &new[y..]);
+            }
+            let at = v.at(k);
+            v.forward[at] = x;
+            if odd && (k - delta).abs() < d && x + v.backward[v.at(delta - k)] >= n {
+                return Some(start);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let (down, up) = (v.at(k - 1), v.at(k + 1));
+            let mut x = match k == -d || (k != d && v.backward[down] < v.backward[up]) {
+                true => v.backward[up],
+                false => v.backward[down] + 1,
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let same = common_suffix(&old[..n - x], &new[..m - y]);
+                x += same;
+                y += same;
+            }
+            let at = v.at(k);
+            v.backward[at] = x;
+            if !odd && (k - delta).abs() <= d && x + v.forward[v.at(delta - k)] >= n {
+                return Some((n - x, m - y));
+            }
+        }
+    }
+    None
+}
+
+fn common_prefix(a: &[&str], b: &[&str]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix(a: &[&str], b: &[&str]) -> usize {
+    a.iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_and_diff() {
+        assert_eq!(
+            normalize(r#"{"b": 1, "a": [true]}"#),
+            normalize("{\n  \"a\": [ true ],\n  \"b\": 1\n}\n")
+        );
+        assert_eq!(normalize("plain \n\n"), "plain");
+        assert_eq!(
+            edits(&["a", "b", "c"], &["a", "x", "c"]),
+            vec![
+                (Op::Same, "a"),
+                (Op::Removed, "b"),
+                (Op::Added, "x"),
+                (Op::Same, "c")
+            ]
+        );
+        let (old, new) = (
+            ["a", "b", "c", "a", "b", "b", "a"],
+            ["c", "b", "a", "b", "a", "c"],
+        );
+        let ops = edits(&old, &new);
+        let side = |skip: Op| -> Vec<&str> {
+            ops.iter()
+                .filter(|(op, _)| *op != skip)
+                .map(|(_, l)| *l)
+                .collect()
+        };
+        assert_eq!(
+            (side(Op::Added), side(Op::Removed)),
+            (old.to_vec(), new.to_vec())
+        );
+        // The longest common subsequence has four lines.
+        assert_eq!(ops.iter().filter(|(op, _)| *op == Op::Same).count(), 4);
+
+        let old: Vec<String> = (0..10).map(|n| n.to_string()).collect();
+        let mut new = old.clone();
+        new[8] = "eight".into();
+        let lines = diff(&old.join("\n"), &new.join("\n"));
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].contains("..."));
+        assert_eq!(lines[1], "  5");
+        assert!(lines[4].contains("- 8") && lines[5].contains("+ eight"));
+    }
+}
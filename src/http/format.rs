@@ -0,0 +1,132 @@
+use super::Captured;
+use crate::{Error, Result};
+use std::str::FromStr;
+
+/// A `--format` string such as `%{status} %{time}ms %{header:Content-Type}`,
+/// printed in place of the response, like curl's `--write-out`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Template(Vec<Segment>);
+
+#[derive(Debug, PartialEq, Clone)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Field {
+    Status,
+    /// Total milliseconds.
+    Time,
+    /// Milliseconds to the response head.
+    Ttfb,
+    /// Body size in bytes.
+    Size,
+    Url,
+    Version,
+    Header(String),
+}
+
+impl FromStr for Field {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "status" => Field::Status,
+            "time" => Field::Time,
+            "ttfb" => Field::Ttfb,
+            "size" => Field::Size,
+            "url" => Field::Url,
+            "version" => Field::Version,
+            _ => match s.strip_prefix("header:") {
+                Some(name) if !name.is_empty() => Field::Header(name.to_string()),
+                _ => {
+                    return Err(format!(
+                        "Unknown placeholder %{{{}}}; expected status, time, ttfb, size, \
+                         url, version or header:NAME",
+                        s
+                    )
+                    .into())
+                }
+            },
+        })
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("%{") {
+            let end = rest[start..]
+                .find('}')
+                .map(|i| i + start)
+                .ok_or_else(|| format!("Unclosed placeholder in {:?}", s))?;
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            segments.push(Segment::Field(rest[start + 2..end].parse()?));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Template(segments))
+    }
+}
+
+impl Template {
+    /// Fills in the placeholders. Missing headers become empty strings.
+    pub(super) fn render(&self, resp: &Captured) -> String {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Field(field) => field.value(resp),
+            })
+            .collect()
+    }
+}
+
+impl Field {
+    fn value(&self, resp: &Captured) -> String {
+        match self {
+            Field::Status => resp.status.as_u16().to_string(),
+            Field::Time => format!("{:.0}", resp.timings.total.unwrap_or_default()),
+            Field::Ttfb => format!("{:.0}", resp.timings.ttfb.unwrap_or_default()),
+            Field::Size => resp.body.len().to_string(),
+            Field::Url => resp.url.to_string(),
+            Field::Version => format!("{:?}", resp.version),
+            Field::Header(name) => resp
+                .headers
+                .get(name.as_str())
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::timing::Timings;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        let mut resp = Captured::for_test(StatusCode::OK, headers, b"hello");
+        resp.received.timings = Timings::new(Duration::from_millis(40), Duration::from_millis(120));
+        let template: Template =
+            "%{status} %{time}ms %{size} [%{header:Content-Type}]%{header:X-None}"
+                .parse()
+                .unwrap();
+        assert_eq!(template.render(&resp), "200 120ms 5 [text/plain]");
+        assert!("%{nope}".parse::<Template>().is_err());
+        assert!("%{status".parse::<Template>().is_err());
+        assert!("%{header:}".parse::<Template>().is_err());
+    }
+}
@@ -1,15 +1,17 @@
-use super::{parse_url, print_resp};
-use crate::Result;
+use super::{client::HttpClient, resolve_url, send, PathParam};
+use crate::{Opts, Result};
 use clap::Args;
-use reqwest::Client;
 
 #[derive(Args, Debug)]
 pub struct Get {
-    #[arg(value_parser = parse_url)]
-    url: String,
+    /// The URL, which may contain {name} placeholders for --path.
+    pub url: String,
+    /// Fill the {name} placeholder in the URL with a percent-encoded value.
+    #[arg(long = "path", value_name = "NAME=VALUE")]
+    pub path_params: Vec<PathParam>,
 }
 
-pub async fn get(client: Client, args: &Get) -> Result<()> {
-    let resp = client.get(&args.url).send().await?;
-    Ok(print_resp(resp).await?)
+pub async fn get(client: &HttpClient, args: &Get, opts: &Opts) -> Result<()> {
+    let url = resolve_url(&args.url, &args.path_params, opts)?;
+    send(client, client.get(url), opts).await
 }
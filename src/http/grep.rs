@@ -0,0 +1,130 @@
+use colored::*;
+use regex::Regex;
+use std::ops::Range;
+use syntect::highlighting::{Color, FontStyle, Style};
+
+/// The look of matched text, drawn over the syntax highlighting.
+const MATCH_STYLE: Style = Style {
+    foreground: Color::BLACK,
+    background: Color {
+        r: 0xeb,
+        g: 0xcb,
+        b: 0x8b,
+        a: 0xff,
+    },
+    font_style: FontStyle::BOLD,
+};
+
+/// Filters a body down to the lines matching a pattern, plus context.
+#[derive(Debug)]
+pub struct Grep<'a> {
+    pub pattern: &'a Regex,
+    pub context: usize,
+}
+
+impl Grep<'_> {
+    /// Marks the lines to print: every match and `context` lines around it.
+    pub fn select(&self, lines: &[&str]) -> Vec<bool> {
+        let mut keep = vec![false; lines.len()];
+        for (i, line) in lines.iter().enumerate() {
+            if self.pattern.is_match(line) {
+                let end = (i + self.context).min(lines.len() - 1);
+                keep[i.saturating_sub(self.context)..=end].fill(true);
+            }
+        }
+        keep
+    }
+
+    fn matches(&self, line: &str) -> Vec<Range<usize>> {
+        self.pattern.find_iter(line).map(|m| m.range()).collect()
+    }
+
+    /// Splits highlighted ranges at match boundaries and restyles the
+    /// matched pieces.
+    pub fn emphasize<'b>(&self, ranges: &[(Style, &'b str)]) -> Vec<(Style, &'b str)> {
+        let line: String = ranges.iter().map(|(_, text)| *text).collect();
+        let matches = self.matches(&line);
+        let mut out = Vec::with_capacity(ranges.len());
+        let mut offset = 0;
+        for &(style, text) in ranges {
+            let mut cursor = 0;
+            for m in &matches {
+                let start = m.start.saturating_sub(offset).max(cursor);
+                let end = m.end.saturating_sub(offset).min(text.len());
+                if start >= end {
+                    continue;
+                }
+                if start > cursor {
+                    out.push((style, &text[cursor..start]));
+                }
+                out.push((MATCH_STYLE, &text[start..end]));
+                cursor = end;
+            }
+            if cursor < text.len() {
+                out.push((style, &text[cursor..]));
+            }
+            offset += text.len();
+        }
+        out
+    }
+
+    /// Highlights matches in a line that has no syntax coloring.
+    pub fn emphasize_plain(&self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut cursor = 0;
+        for m in self.matches(line) {
+            out.push_str(&line[cursor..m.start]);
+            out.push_str(&line[m.clone()].black().on_yellow().bold().to_string());
+            cursor = m.end;
+        }
+        out.push_str(&line[cursor..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_with_context() {
+        let re = Regex::new("id").unwrap();
+        let lines = [
+            "{",
+            "  \"a\": 1,",
+            "  \"id\": 2,",
+            "  \"b\": 3,",
+            "  \"c\": 4",
+            "}",
+        ];
+        let grep = Grep {
+            pattern: &re,
+            context: 0,
+        };
+        assert_eq!(
+            grep.select(&lines),
+            [false, false, true, false, false, false]
+        );
+        let grep = Grep {
+            pattern: &re,
+            context: 1,
+        };
+        assert_eq!(grep.select(&lines), [false, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_emphasize_splits_ranges() {
+        let re = Regex::new("bc").unwrap();
+        let grep = Grep {
+            pattern: &re,
+            context: 0,
+        };
+        let plain = Style::default();
+        let out = grep.emphasize(&[(plain, "ab"), (plain, "cd")]);
+        let pieces: Vec<_> = out.iter().map(|(s, t)| (*s == MATCH_STYLE, *t)).collect();
+        assert_eq!(
+            pieces,
+            [(false, "a"), (true, "b"), (true, "c"), (false, "d")]
+        );
+    }
+}
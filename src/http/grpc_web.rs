@@ -0,0 +1,192 @@
+//! Best-effort display of gRPC-web response bodies for `--grpc-web`.
+//!
+//! A body is a sequence of frames, each a flag byte (`0x80` for the
+//! trailer frame), a big-endian `u32` length and the payload. The
+//! `application/grpc-web-text` variant base64-encodes all of it. Messages
+//! are shown as raw protobuf fields since there is no schema to go by.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use mime::Mime;
+use std::fmt::Write;
+
+const TRAILER_FLAG: u8 = 0x80;
+
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub trailers: bool,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(Vec<u8>),
+    Fixed32(u32),
+}
+
+pub fn is_grpc_web(m: &Mime) -> bool {
+    m.subtype().as_str().starts_with("grpc-web")
+}
+
+/// Splits a body into frames, decoding base64 first when the body is not
+/// binary framing. Returns `None` when neither reading works.
+pub fn frames(body: &[u8]) -> Option<Vec<Frame>> {
+    split(body).or_else(|| split(&decode_text(body)?))
+}
+
+/// Each frame of grpc-web-text is padded base64 on its own, so the body is
+/// decoded four characters at a time.
+fn decode_text(body: &[u8]) -> Option<Vec<u8>> {
+    let body: Vec<u8> = body
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if !body.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(body.len() / 4 * 3);
+    for quad in body.chunks(4) {
+        out.extend(STANDARD.decode(quad).ok()?);
+    }
+    Some(out)
+}
+
+fn split(mut body: &[u8]) -> Option<Vec<Frame>> {
+    let mut frames = Vec::new();
+    while !body.is_empty() {
+        let flags = *body.first()?;
+        if flags & !TRAILER_FLAG != 0 {
+            return None;
+        }
+        let len = u32::from_be_bytes(body.get(1..5)?.try_into().ok()?) as usize;
+        let payload = body.get(5..5 + len)?;
+        frames.push(Frame {
+            trailers: flags & TRAILER_FLAG != 0,
+            payload: payload.to_vec(),
+        });
+        body = &body[5 + len..];
+    }
+    (!frames.is_empty()).then_some(frames)
+}
+
+/// Reads a message as protobuf fields, or `None` if it is not valid wire
+/// format.
+fn fields(mut data: &[u8]) -> Option<Vec<(u64, Value)>> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = varint(&mut data)?;
+        let value = match key & 7 {
+            0 => Value::Varint(varint(&mut data)?),
+            1 => Value::Fixed64(u64::from_le_bytes(take(&mut data, 8)?.try_into().ok()?)),
+            2 => {
+                let len = varint(&mut data)? as usize;
+                Value::Bytes(take(&mut data, len)?.to_vec())
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(take(&mut data, 4)?.try_into().ok()?)),
+            _ => return None,
+        };
+        fields.push((key >> 3, value));
+    }
+    Some(fields)
+}
+
+fn varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *take(data, 1)?.first()?;
+        value |= u64::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    let (head, rest) = (data.get(..n)?, data.get(n..)?);
+    *data = rest;
+    Some(head)
+}
+
+/// Describes each frame: trailers as text, messages as their fields, and
+/// anything else as hex.
+pub fn render(frames: &[Frame]) -> String {
+    let mut out = String::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let kind = if frame.trailers {
+            "trailers"
+        } else {
+            "message"
+        };
+        let _ = writeln!(
+            out,
+            "frame {}: {}, {} bytes",
+            i + 1,
+            kind,
+            frame.payload.len()
+        );
+        if frame.trailers {
+            for line in String::from_utf8_lossy(&frame.payload).lines() {
+                let _ = writeln!(out, "  {}", line);
+            }
+            continue;
+        }
+        match fields(&frame.payload) {
+            Some(fields) => {
+                for (number, value) in fields {
+                    let _ = writeln!(out, "  {}: {}", number, describe(&value));
+                }
+            }
+            None => {
+                let _ = writeln!(out, "  {}", hex::encode(&frame.payload));
+            }
+        }
+    }
+    out
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Varint(n) => format!("varint {}", n),
+        Value::Fixed64(n) => format!("fixed64 {}", n),
+        Value::Fixed32(n) => format!("fixed32 {}", n),
+        // Strings and nested messages share a wire type; text is the
+        // likelier reading when it decodes cleanly.
+        Value::Bytes(b) => match std::str::from_utf8(b) {
+            Ok(s) if !s.chars().any(char::is_control) => format!("string {:?}", s),
+            _ => format!("bytes {}", hex::encode(b)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A message with field 1 = 150 and field 2 = "hi", then trailers.
+    const BODY: &[u8] = b"\x00\x00\x00\x00\x07\x08\x96\x01\x12\x02hi\
+                          \x80\x00\x00\x00\x0egrpc-status:0\n";
+
+    #[test]
+    fn test_frames() {
+        let frames = frames(BODY).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[1].trailers);
+        assert_eq!(
+            render(&frames),
+            "frame 1: message, 7 bytes\n  1: varint 150\n  2: string \"hi\"\n\
+             frame 2: trailers, 14 bytes\n  grpc-status:0\n"
+        );
+        // Text mode pads each frame's base64 separately.
+        let text = format!(
+            "{}{}",
+            STANDARD.encode(&BODY[..12]),
+            STANDARD.encode(&BODY[12..])
+        );
+        assert_eq!(super::frames(text.as_bytes()).unwrap(), frames);
+        assert!(super::frames(b"not grpc").is_none());
+        assert!(super::frames(b"\x00\x00\x00\x00\x09short").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+//! Reading a response. Buffered output, `--download` and `--no-buffer`
+//! differ only in where the body goes, so they share the sending, timing
+//! and frame loop here and pass in what to do with each chunk.
+
+use super::client::{HttpClient, Limited};
+use super::timing::Timings;
+use crate::Result;
+use http_body_util::BodyExt;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Request, StatusCode, Url, Version};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+/// A response whose head has arrived but whose body has not been read.
+pub struct Incoming {
+    resp: Limited,
+    start: Instant,
+    ttfb: Duration,
+}
+
+/// What is known about a response once its body has ended, wherever the
+/// body went.
+pub struct Received {
+    /// The method and URL of the last request, after any redirects.
+    pub method: Method,
+    pub url: Url,
+    pub version: Version,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// Body bytes as received, still in any `Content-Encoding`.
+    pub bytes: u64,
+    /// Headers sent after a chunked body; empty when there were none.
+    pub trailers: HeaderMap,
+    pub timings: Timings,
+}
+
+impl Incoming {
+    /// Sends the request, following redirects, and waits for the head.
+    pub async fn send(client: &HttpClient, req: Request) -> Result<Self> {
+        let start = Instant::now();
+        let resp = client.send(req).await?;
+        Ok(Self {
+            resp,
+            start,
+            ttfb: start.elapsed(),
+        })
+    }
+
+    /// Reads the body, handing each chunk to `sink` as it arrives.
+    pub async fn read(self, mut sink: impl FnMut(&[u8]) -> Result<()>) -> Result<Received> {
+        let method = self.resp.method().clone();
+        let (url, version, status) = (self.url().clone(), self.version(), self.status());
+        let headers = self.headers().clone();
+        // The connection slot is held until the body has been read.
+        let (resp, _permit) = self.resp.into_parts();
+        // Going through http_body keeps the trailer frame that bytes() drops.
+        let mut body = ::http::Response::from(resp).into_body();
+        let mut bytes = 0;
+        let mut trailers = HeaderMap::new();
+        while let Some(frame) = body.frame().await {
+            match frame?.into_data() {
+                Ok(chunk) => {
+                    sink(&chunk)?;
+                    bytes += chunk.len() as u64;
+                }
+                Err(frame) => {
+                    if let Ok(more) = frame.into_trailers() {
+                        trailers.extend(more);
+                    }
+                }
+            }
+        }
+        Ok(Received {
+            method,
+            url,
+            version,
+            status,
+            headers,
+            bytes,
+            trailers,
+            timings: Timings::new(self.ttfb, self.start.elapsed()),
+        })
+    }
+}
+
+impl Deref for Incoming {
+    type Target = reqwest::Response;
+
+    fn deref(&self) -> &reqwest::Response {
+        &self.resp
+    }
+}
@@ -0,0 +1,77 @@
+use super::timing::Timings;
+use super::Captured;
+use crate::Result;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The machine-readable shape printed by `--output-format json`.
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    url: &'a str,
+    status: u16,
+    version: String,
+    headers: Map<String, Value>,
+    body: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<&'a Timings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<&'a str>,
+}
+
+pub fn print(resp: &Captured, timing: bool, fingerprint: Option<&str>) -> Result<()> {
+    let text = resp.text()?;
+    let output = JsonOutput {
+        url: resp.url.as_str(),
+        status: resp.status.as_u16(),
+        version: format!("{:?}", resp.version),
+        headers: headers(&resp.headers),
+        body: body_value(text),
+        timings: timing.then_some(&resp.timings),
+        fingerprint,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// Parses a body as JSON, keeping bodies that are not JSON verbatim as a
+/// string.
+pub fn body_value(text: String) -> Value {
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+/// Maps each header to its value, or to an array when it was repeated.
+fn headers(headers: &HeaderMap) -> Map<String, Value> {
+    let mut map = Map::new();
+    for name in headers.keys() {
+        let mut values: Vec<Value> = headers
+            .get_all(name)
+            .iter()
+            .map(|v| Value::String(String::from_utf8_lossy(v.as_bytes()).into()))
+            .collect();
+        let value = match values.len() {
+            1 => values.remove(0),
+            _ => Value::Array(values),
+        };
+        map.insert(name.to_string(), value);
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, SET_COOKIE};
+
+    #[test]
+    fn test_repeated_headers_become_arrays() {
+        let mut h = HeaderMap::new();
+        h.insert("content-type", HeaderValue::from_static("text/plain"));
+        h.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+        h.append(SET_COOKIE, HeaderValue::from_static("b=2"));
+        assert_eq!(
+            Value::Object(headers(&h)),
+            serde_json::json!({"content-type": "text/plain", "set-cookie": ["a=1", "b=2"]})
+        );
+    }
+}
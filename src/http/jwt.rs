@@ -0,0 +1,103 @@
+//! Decoding of JWT-shaped header values for `--decode-jwt`. Signatures are
+//! not verified; this only makes the claims readable.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::DateTime;
+use colored::*;
+use regex::Regex;
+use reqwest::header::HeaderValue;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Three base64url segments, the first two JSON objects and so starting
+/// with `eyJ` (`{"`). The signature may be empty for unsigned tokens.
+static TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"eyJ[A-Za-z0-9_-]*\.eyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]*").unwrap());
+
+/// Claims holding seconds since the epoch, shown as dates too.
+const TIME_CLAIMS: [&str; 3] = ["exp", "iat", "nbf"];
+
+#[derive(Debug, PartialEq)]
+pub struct Jwt {
+    pub header: Value,
+    pub claims: Value,
+}
+
+/// Finds and decodes every JWT in a header value, such as a bearer token
+/// or a cookie.
+pub fn find(value: &HeaderValue) -> Vec<Jwt> {
+    let Ok(value) = value.to_str() else {
+        return Vec::new();
+    };
+    TOKEN
+        .find_iter(value)
+        .filter_map(|m| decode(m.as_str()))
+        .collect()
+}
+
+fn decode(token: &str) -> Option<Jwt> {
+    let mut parts = token.split('.');
+    let mut segment = || -> Option<Value> {
+        let bytes = URL_SAFE_NO_PAD.decode(parts.next()?).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    };
+    Some(Jwt {
+        header: segment()?,
+        claims: segment()?,
+    })
+}
+
+/// Prints the decoded token below the header it came from, indented.
+pub fn print(jwt: &Jwt) {
+    meta!(
+        "{}",
+        indent(&format!("JWT header: {}", jwt.header)).dimmed()
+    );
+    let claims = serde_json::to_string_pretty(&jwt.claims).unwrap_or_default();
+    meta!("{}", indent(&format!("JWT claims: {}", claims)).dimmed());
+    for name in TIME_CLAIMS {
+        if let Some(time) = jwt.claims.get(name).and_then(timestamp) {
+            meta!("{}", indent(&format!("{}: {}", name, time)).dimmed());
+        }
+    }
+}
+
+fn timestamp(value: &Value) -> Option<String> {
+    let time = DateTime::from_timestamp(value.as_i64()?, 0)?;
+    Some(time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
+fn indent(s: &str) -> String {
+    s.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        // {"alg":"HS256","typ":"JWT"}.{"sub":"1234567890","iat":1516239022}
+        let token = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                     eyJzdWIiOiIxMjM0NTY3ODkwIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+                     SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+        let value = HeaderValue::from_str(&format!("Bearer {}", token)).unwrap();
+        assert_eq!(
+            find(&value),
+            vec![Jwt {
+                header: serde_json::json!({"alg": "HS256", "typ": "JWT"}),
+                claims: serde_json::json!({"sub": "1234567890", "iat": 1516239022}),
+            }]
+        );
+        let cookie = HeaderValue::from_str(&format!("session={}; Path=/", token)).unwrap();
+        assert_eq!(find(&cookie).len(), 1);
+        assert!(find(&HeaderValue::from_static("Bearer abc.def.ghi")).is_empty());
+        assert_eq!(
+            timestamp(&serde_json::json!(1516239022)).unwrap(),
+            "2018-01-18 01:30:22 UTC"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! The last response body, kept for chaining one request into the next.
+//!
+//! Every response that is not downloaded overwrites
+//! `httpie/last-response` in the user's cache directory, unless
+//! `--no-save-last` is given. The file lives until the next response
+//! replaces it or the cache is cleaned; delete it to clear it. Post bodies
+//! read it back with `--body @last` or with `$LAST` field values. A
+//! leading UTF-8 byte order mark is dropped on the way back, as it is when
+//! the response is printed, so BOM-prefixed JSON still parses.
+
+use crate::json_path;
+use crate::Result;
+use colored::*;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+const VARIABLE: &str = "$LAST";
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+pub fn path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("httpie").join("last-response"))
+}
+
+/// Stores `body`, warning rather than failing since the request itself
+/// succeeded.
+pub fn save(body: &[u8]) {
+    let Some(path) = path() else {
+        return;
+    };
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| write_private(&path, body));
+    if let Err(e) = saved {
+        meta!(
+            "{} could not save the response to {}: {}",
+            "Warning:".yellow(),
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Responses may carry secrets, so the file is readable only by its owner.
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, body: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(body)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &std::path::Path, body: &[u8]) -> std::io::Result<()> {
+    fs::write(path, body)
+}
+
+pub fn load() -> Result<Vec<u8>> {
+    let path = path().ok_or("No cache directory to read the last response from")?;
+    let body = fs::read(&path).map_err(|e| {
+        format!(
+            "No last response to use ({}: {}); send a request first",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(strip_bom(body))
+}
+
+fn strip_bom(mut body: Vec<u8>) -> Vec<u8> {
+    if body.starts_with(BOM) {
+        body.drain(..BOM.len());
+    }
+    body
+}
+
+/// Splits a field value such as `$LAST.data.id` into its JSON path, or
+/// `None` when the value is not a `$LAST` reference.
+pub fn reference(value: &str) -> Option<&str> {
+    let path = value.strip_prefix(VARIABLE)?;
+    (path.is_empty() || path.starts_with(['.', '['])).then_some(path)
+}
+
+/// The value at `path` in the last response.
+pub fn value(path: &str) -> Result<Value> {
+    select(&load()?, path)
+}
+
+fn select(body: &[u8], path: &str) -> Result<Value> {
+    let doc: Value = serde_json::from_slice(body)
+        .map_err(|e| format!("{} needs a JSON response: {}", VARIABLE, e))?;
+    json_path::select(&doc, path)
+        .cloned()
+        .ok_or_else(|| format!("{}{} is not in the last response", VARIABLE, path).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reference() {
+        assert_eq!(reference("$LAST"), Some(""));
+        assert_eq!(reference("$LAST.data.id"), Some(".data.id"));
+        assert_eq!(reference("$LAST[0]"), Some("[0]"));
+        assert_eq!(reference("$LASTING"), None);
+        assert_eq!(reference("id"), None);
+
+        let body = br#"{"data": {"id": 7}}"#;
+        assert_eq!(select(body, ".data.id").unwrap(), json!(7));
+        assert_eq!(select(body, "").unwrap(), json!({"data": {"id": 7}}));
+        assert!(select(body, ".nope").is_err());
+        assert!(select(b"plain", "").is_err());
+    }
+
+    #[test]
+    fn test_strip_bom() {
+        let body = strip_bom(b"\xEF\xBB\xBF{\"id\": 7}".to_vec());
+        assert_eq!(select(&body, ".id").unwrap(), json!(7));
+        assert_eq!(strip_bom(b"plain".to_vec()), b"plain");
+    }
+}
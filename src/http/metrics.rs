@@ -0,0 +1,151 @@
+//! Prometheus text-format metrics for `--metrics-file`.
+//!
+//! Each request records three gauges labelled with the method and URL of
+//! the request that got the final response, after any redirects:
+//!
+//! ```text
+//! httpie_request_duration_seconds{method="GET",url="https://example.com/"} 0.084
+//! httpie_response_status{method="GET",url="https://example.com/"} 200
+//! httpie_response_bytes{method="GET",url="https://example.com/"} 1256
+//! ```
+//!
+//! The file is rewritten rather than appended to: a request replaces the
+//! series with the same labels and adds any new ones, and each metric's
+//! samples stay together under one `# HELP` and `# TYPE`, as the textfile
+//! collector of node_exporter requires. The new contents go to a temporary
+//! file that is renamed over the old one, so a reader never sees half a
+//! file. Lines for other metrics are not kept, so give httpie a file of its
+//! own.
+
+use super::incoming::Received;
+use crate::Result;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+const METRICS: [(&str, &str); 3] = [
+    (
+        "httpie_request_duration_seconds",
+        "Time from sending the request to reading the whole response.",
+    ),
+    (
+        "httpie_response_status",
+        "HTTP status code of the response.",
+    ),
+    (
+        "httpie_response_bytes",
+        "Size of the response body in bytes.",
+    ),
+];
+
+/// Records the request in the metrics file at `path`.
+pub fn update(path: &Path, resp: &Received) -> Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let fail = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    fs::write(&tmp, merge(&existing, resp)).map_err(fail)?;
+    fs::rename(&tmp, path).map_err(fail)?;
+    Ok(())
+}
+
+/// Rebuilds the file's text with this request's series in place of any
+/// earlier ones with the same labels. Series keep the order they were
+/// first recorded in.
+fn merge(existing: &str, resp: &Received) -> String {
+    let mut series: Vec<Vec<(String, String)>> = vec![Vec::new(); METRICS.len()];
+    for line in existing.lines().filter(|l| !l.starts_with('#')) {
+        let Some((sample, value)) = line.rsplit_once(' ') else {
+            continue;
+        };
+        let Some((name, labels)) = sample.split_once('{') else {
+            continue;
+        };
+        if let Some(i) = METRICS.iter().position(|(n, _)| *n == name) {
+            series[i].push((format!("{{{}", labels), value.to_string()));
+        }
+    }
+
+    let labels = format!(
+        "{{method=\"{}\",url=\"{}\"}}",
+        escape(resp.method.as_str()),
+        escape(resp.url.as_str())
+    );
+    let seconds = resp.timings.total.unwrap_or_default() / 1000.0;
+    let values = [
+        seconds.to_string(),
+        resp.status.as_u16().to_string(),
+        resp.bytes.to_string(),
+    ];
+    for (samples, value) in series.iter_mut().zip(values) {
+        match samples.iter_mut().find(|(l, _)| *l == labels) {
+            Some(sample) => sample.1 = value,
+            None => samples.push((labels.clone(), value)),
+        }
+    }
+
+    let mut out = String::new();
+    for ((name, help), samples) in METRICS.iter().zip(series) {
+        out += &format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name);
+        for (labels, value) in samples {
+            out += &format!("{}{} {}\n", name, labels, value);
+        }
+    }
+    out
+}
+
+/// Escapes a label value as the text exposition format requires.
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::timing::Timings;
+    use crate::http::Captured;
+    use reqwest::header::HeaderMap;
+    use reqwest::{Method, StatusCode, Url};
+    use std::time::Duration;
+
+    #[test]
+    fn test_merge() {
+        let mut resp = Captured::for_test(StatusCode::NOT_FOUND, HeaderMap::new(), b"missing");
+        resp.received.url = Url::parse("http://localhost/a?q=\"x\"").unwrap();
+        resp.received.timings = Timings::new(Duration::ZERO, Duration::from_millis(250));
+        let labels = r#"{method="GET",url="http://localhost/a?q=%22x%22"}"#;
+        let first = merge("", &resp);
+        assert_eq!(first.matches("# TYPE").count(), 3);
+        assert!(first.contains(&format!("httpie_response_status{} 404\n", labels)));
+
+        // Another URL joins each metric's block; the same URL replaces its
+        // series.
+        let mut other = Captured::for_test(StatusCode::OK, HeaderMap::new(), b"");
+        other.received.method = Method::POST;
+        other.received.url = Url::parse("http://localhost/b").unwrap();
+        let second = merge(&first, &other);
+        resp.received.status = StatusCode::OK;
+        let third = merge(&second, &resp);
+        assert_eq!(third.matches("# TYPE").count(), 3);
+        assert_eq!(
+            third
+                .lines()
+                .filter(|l| l.starts_with("httpie_response_status"))
+                .collect::<Vec<_>>(),
+            [
+                format!("httpie_response_status{} 200", labels),
+                r#"httpie_response_status{method="POST",url="http://localhost/b"} 200"#.into()
+            ]
+        );
+        assert!(third
+            .starts_with("# HELP httpie_request_duration_seconds Time from sending the request"));
+        assert!(third.contains(&format!("httpie_request_duration_seconds{} 0.25\n", labels)));
+        assert_eq!(escape("a\"b\\c"), r#"a\"b\\c"#);
+    }
+}
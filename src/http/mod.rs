@@ -1,82 +1,765 @@
+pub mod assertion;
+mod auth;
+pub mod client;
+mod collapse;
+pub mod compare;
+mod compression;
+pub mod curl;
+mod download;
+mod env;
+pub mod explain;
+mod fingerprint;
+mod fixture;
+pub mod format;
 pub mod get;
+mod grep;
+mod grpc_web;
+mod incoming;
+mod json_output;
+mod jwt;
+mod last;
+mod metrics;
+mod openapi;
 pub mod post;
+pub mod probe;
+pub mod redirect;
+pub mod sigv4;
+mod stream;
+mod template;
+mod timing;
+mod wire;
 
-use crate::Result;
+use crate::{output, Opts, Result};
 
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
+use client::HttpClient;
 use colored::*;
+use compare::Compare;
+use compression::Compression;
+pub use download::parse_sha256;
+use encoding_rs::{Encoding, UTF_8};
 use get::Get;
+use grep::Grep;
+use incoming::{Incoming, Received};
 use mime::Mime;
 use post::Post;
-use reqwest::{header, Response, Url};
+use probe::Probe;
+use regex::Regex;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Request, RequestBuilder, StatusCode, Url, Version};
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::io::{self, IsTerminal};
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Deref;
+use std::time::Duration;
+pub use template::PathParam;
+use timing::Timings;
+use uuid::Uuid;
 
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{Style, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
 
+const REQUEST_ID: &str = "X-Request-Id";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Colored status line, headers and body.
+    #[default]
+    Text,
+    /// A single JSON document describing the response.
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Method {
     Get(Get),
     Post(Post),
+    /// Check which schemes and common ports of a host respond.
+    Probe(Probe),
+    /// Fetch the same path from several environments side by side.
+    Compare(Compare),
 }
 pub fn parse_url(s: &str) -> Result<String> {
+    let s = encode_path(s);
     let _url: Url = s.parse()?;
-    Ok(s.into())
+    Ok(s)
+}
+
+/// Expands `$NAME` references under `--expand-env-in-url`, fills in the
+/// `{name}` placeholders of a URL template from `--path` values, then
+/// parses the result. The environment goes first so that `${NAME}` is not
+/// read as a placeholder; its values are inserted as is, so they may hold
+/// whole hosts or paths.
+pub fn resolve_url(template: &str, params: &[PathParam], opts: &Opts) -> Result<String> {
+    let template = match opts.expand_env_in_url {
+        true => env::expand(template, opts.allow_unset_env)?,
+        false => template.to_string(),
+    };
+    parse_url(&template::expand(&template, params)?)
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986,
+/// so the result is safe anywhere in a URL.
+fn encode_component(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Punctuation RFC 3986 allows unescaped in a path.
+const PATH_CHARS: &str = "/-._~!$&'()*+,;=:@";
+
+/// Percent-encodes the characters of the URL's path that may not appear
+/// there unescaped, such as spaces, non-ASCII text and stray `%` signs.
+/// Existing `%XX` escapes are kept, so encoding twice changes nothing.
+fn encode_path(url: &str) -> String {
+    let Some(authority) = url.find("://").map(|i| i + 3) else {
+        return url.into();
+    };
+    let Some(start) = url[authority..].find('/').map(|i| i + authority) else {
+        return url.into();
+    };
+    let end = url[start..]
+        .find(['?', '#'])
+        .map_or(url.len(), |i| i + start);
+
+    let path = &url[start..end];
+    let mut out = String::with_capacity(url.len());
+    out.push_str(&url[..start]);
+    for (i, c) in path.char_indices() {
+        let escape = path.as_bytes().get(i + 1..i + 3);
+        let escaped = c == '%' && escape.is_some_and(|e| e.iter().all(u8::is_ascii_hexdigit));
+        if escaped || c.is_ascii_alphanumeric() || PATH_CHARS.contains(c) {
+            out.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    out.push_str(&url[end..]);
+    out
+}
+
+pub fn parse_secs(s: &str) -> Result<Duration> {
+    Ok(Duration::try_from_secs_f64(s.parse()?)?)
+}
+
+/// Checks an `Accept-Language` list such as `en-US, fr;q=0.8, *;q=0.1`
+/// and normalizes its spacing. Tags are checked loosely, as letters and
+/// digits in subtags of up to eight characters.
+pub fn parse_accept_language(s: &str) -> Result<String> {
+    let mut ranges = Vec::new();
+    for item in s.split(',').map(str::trim) {
+        let (tag, q) = match item.split_once(';') {
+            Some((tag, q)) => (tag.trim(), Some(q.trim())),
+            None => (item, None),
+        };
+        let valid_tag = tag == "*"
+            || tag.split('-').all(|sub| {
+                (1..=8).contains(&sub.len()) && sub.chars().all(|c| c.is_ascii_alphanumeric())
+            });
+        if !valid_tag {
+            return Err(format!("{:?} is not a language tag", tag).into());
+        }
+        match q {
+            None => ranges.push(tag.to_string()),
+            Some(q) => {
+                let weight = q
+                    .strip_prefix("q=")
+                    .and_then(|w| w.parse::<f32>().ok())
+                    .filter(|w| (0.0..=1.0).contains(w))
+                    .ok_or_else(|| format!("{:?} is not a weight like q=0.8", q))?;
+                ranges.push(format!("{};q={}", tag, weight));
+            }
+        }
+    }
+    Ok(ranges.join(", "))
+}
+
+/// Parses a `--bind` address. reqwest can pick the local interface but not
+/// the port, so a port is rejected rather than silently ignored.
+pub fn parse_bind(s: &str) -> Result<IpAddr> {
+    if let Ok(ip) = s.parse() {
+        return Ok(ip);
+    }
+    match s.parse::<SocketAddr>() {
+        Ok(addr) => Err(format!(
+            "Binding to local port {} is not supported; give only the address {}",
+            addr.port(),
+            addr.ip()
+        )
+        .into()),
+        Err(_) => Err(format!("{:?} is not an IP address", s).into()),
+    }
+}
+
+/// What a repeated request waits for in the response body.
+enum BodyMatch<'a> {
+    Contains(&'a str),
+    Regex(&'a Regex),
+}
+
+impl BodyMatch<'_> {
+    fn from_opts(opts: &Opts) -> Option<BodyMatch<'_>> {
+        match (
+            &opts.repeat_until_body_contains,
+            &opts.repeat_until_body_matches,
+        ) {
+            (Some(s), _) => Some(BodyMatch::Contains(s)),
+            (_, Some(re)) => Some(BodyMatch::Regex(re)),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` when the body does not match, otherwise the text worth
+    /// echoing for the attempt: the first capture group if the regex has one.
+    fn find<'b>(&self, body: &'b str) -> Option<&'b str> {
+        match self {
+            BodyMatch::Contains(s) => body.find(s).map(|i| &body[i..i + s.len()]),
+            BodyMatch::Regex(re) => re
+                .captures(body)
+                .map(|c| c.get(1).or_else(|| c.get(0)).unwrap().as_str()),
+        }
+    }
 }
 
-async fn print_resp(resp: Response) -> Result<()> {
-    print_status(&resp)?;
-    print_headers(&resp)?;
+/// A response whose body has been read in full and kept in memory.
+struct Captured {
+    received: Received,
+    /// The body as received, still in any `Content-Encoding`.
+    body: Vec<u8>,
+    /// The body with its `Content-Encoding` undone, filled on first read;
+    /// `None` when it was not compressed.
+    decoded: OnceCell<Option<(Vec<u8>, Compression)>>,
+}
 
-    let mime = get_content_type(&resp);
-    let body = resp.text().await?;
-    print_body(mime, &body)?;
+impl Captured {
+    /// A response to http://localhost/ for unit tests, which set any other
+    /// field they care about.
+    #[cfg(test)]
+    fn for_test(status: StatusCode, headers: HeaderMap, body: &[u8]) -> Self {
+        Self {
+            received: Received {
+                method: reqwest::Method::GET,
+                url: Url::parse("http://localhost/").unwrap(),
+                version: reqwest::Version::HTTP_11,
+                status,
+                headers,
+                bytes: body.len() as u64,
+                trailers: HeaderMap::new(),
+                timings: timing::Timings::default(),
+            },
+            body: body.to_vec(),
+            decoded: OnceCell::new(),
+        }
+    }
+
+    /// Sends the request and reads the whole response, timing both steps.
+    async fn fetch(client: &HttpClient, req: Request) -> Result<Self> {
+        let mut body = Vec::new();
+        let received = Incoming::send(client, req)
+            .await?
+            .read(|chunk| {
+                body.extend_from_slice(chunk);
+                Ok(())
+            })
+            .await?;
+        Ok(Self {
+            received,
+            body,
+            decoded: OnceCell::new(),
+        })
+    }
+
+    fn mime(&self) -> Option<Mime> {
+        get_content_type(&self.headers)
+    }
+
+    /// The body with its `Content-Encoding` undone, and how it was
+    /// compressed. The body is decompressed once and kept for later reads.
+    fn decoded(&self) -> Result<(&[u8], Option<&Compression>)> {
+        if self.decoded.get().is_none() {
+            let (body, compression) = compression::decode(&self.headers, &self.body)?;
+            let body = match body {
+                Cow::Owned(body) => Some(body),
+                Cow::Borrowed(_) => None,
+            };
+            let _ = self.decoded.set(body.zip(compression));
+        }
+        Ok(match self.decoded.get() {
+            Some(Some((body, compression))) => (body, Some(compression)),
+            _ => (&self.body, None),
+        })
+    }
+
+    /// Decodes the body using the charset of its content type, or UTF-8.
+    /// A leading byte order mark is dropped, and overrides the charset, so
+    /// BOM-prefixed JSON still pretty-prints and parses.
+    fn text(&self) -> Result<String> {
+        let encoding = self
+            .mime()
+            .and_then(|m| {
+                let charset = m.get_param(mime::CHARSET)?;
+                Encoding::for_label(charset.as_str().as_bytes())
+            })
+            .unwrap_or(UTF_8);
+        let (body, _) = self.decoded()?;
+        Ok(encoding.decode(body).0.into_owned())
+    }
+}
+
+impl Deref for Captured {
+    type Target = Received;
+
+    fn deref(&self) -> &Received {
+        &self.received
+    }
+}
+
+/// Sends the request and prints the response, honoring the repeat options.
+pub async fn send(client: &HttpClient, req: RequestBuilder, opts: &Opts) -> Result<()> {
+    let request_id = opts
+        .request_id
+        .as_ref()
+        .map(|id| id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()));
+    // Servers may only send trailers to clients that say they accept them.
+    let req = match opts.trailers {
+        true => req.header(header::TE, "trailers"),
+        false => req,
+    };
+    let req = match &opts.accept_language {
+        Some(languages) => req.header(header::ACCEPT_LANGUAGE, languages),
+        None => req,
+    };
+    let req = match opts.show_compression {
+        true => req.header(header::ACCEPT_ENCODING, compression::ACCEPT),
+        false => req,
+    };
+    let mut req = req.build()?;
+    // Taken before the per-run request id and credentials are added.
+    let fingerprint = opts.fingerprint.then(|| fingerprint::fingerprint(&req));
+    if let Some(id) = &request_id {
+        req.headers_mut()
+            .insert(REQUEST_ID, HeaderValue::from_str(id)?);
+    }
+    auth::apply(&mut req, opts)?;
+    if opts.print_wire {
+        meta!("{}\n", wire::render(&req).dimmed());
+    }
+    if opts.decode_jwt {
+        print_request_jwts(req.headers());
+    }
+    if opts.curl || opts.dry_run_curl_to_clipboard {
+        let command = curl::render(&req, opts.curl_unredacted)?;
+        if opts.dry_run_curl_to_clipboard {
+            curl::copy(&command)?;
+            eprintln!("{}", "Copied the curl command to the clipboard".green());
+        } else {
+            println!("{}", command);
+        }
+        return Ok(());
+    }
+
+    // JSON output carries it as a field, so that stdout stays one document.
+    if let Some(fingerprint) = fingerprint.as_ref().filter(|_| !prints_json(opts)) {
+        meta!("{} {}\n", "Fingerprint:".yellow(), fingerprint);
+    }
+
+    let example = opts
+        .as_openapi_example
+        .then(|| openapi::RequestExample::capture(&req));
+    if opts.warmup {
+        warmup(client, req.url()).await;
+    }
+
+    if opts.download {
+        let saved = download::fetch(client, req, opts).await?;
+        check_received(&saved.received, request_id.as_deref(), opts)?;
+        download::report(&saved, opts)?;
+        return check_empty(saved.received.bytes, opts);
+    }
+    if opts.no_buffer {
+        if opts.output_format == OutputFormat::Json {
+            return Err("--no-buffer cannot be used with --output-format json".into());
+        }
+        let streamed = stream::fetch(client, req, opts).await?;
+        check_received(&streamed, request_id.as_deref(), opts)?;
+        if opts.trailers {
+            print_trailers(&streamed.trailers, opts)?;
+        }
+        if opts.timing {
+            print_timings(&streamed.timings);
+        }
+        return check_empty(streamed.bytes, opts);
+    }
+    let resp = match BodyMatch::from_opts(opts) {
+        Some(m) => repeat_until(client, req, m, opts).await?,
+        None => Captured::fetch(client, req).await?,
+    };
+    check_received(&resp, request_id.as_deref(), opts)?;
+    if let Some(path) = &opts.tee {
+        download::tee(&resp, path)?;
+    }
+    if !opts.no_save_last {
+        last::save(resp.decoded()?.0);
+    }
+    if let Some(example) = &example {
+        openapi::print(example, &resp)?;
+    } else if let Some(template) = &opts.format {
+        println!("{}", template.render(&resp));
+    } else if opts.count {
+        println!("{}", count_elements(&resp.text()?)?);
+    } else {
+        match opts.output_format {
+            OutputFormat::Json => json_output::print(&resp, opts.timing, fingerprint.as_deref())?,
+            OutputFormat::Text => {
+                print_resp(&resp, opts)?;
+                if opts.trailers {
+                    print_trailers(&resp.trailers, opts)?;
+                }
+                if opts.show_compression {
+                    let (body, compression) = resp.decoded()?;
+                    meta!(
+                        "\n{} {}",
+                        "Compression:".yellow(),
+                        compression::describe(compression, body.len())
+                    );
+                }
+                if opts.timing {
+                    print_timings(&resp.timings);
+                }
+            }
+        }
+    }
+    check_expectations(&resp, opts)
+}
+
+/// Whether the response is printed by `--output-format json`, rather than
+/// by an option that takes its place.
+fn prints_json(opts: &Opts) -> bool {
+    opts.output_format == OutputFormat::Json
+        && !(opts.download || opts.as_openapi_example || opts.format.is_some() || opts.count)
+}
+
+/// Warns about the response head, echoes the request id and records the
+/// metrics, however the body was read.
+fn check_received(resp: &Received, request_id: Option<&str>, opts: &Opts) -> Result<()> {
+    warn_if_prefers_https(&resp.url, &resp.headers);
+    if let Some(id) = request_id {
+        print_request_id(id, &resp.headers);
+    }
+    if let Some(path) = &opts.metrics_file {
+        metrics::update(path, resp)?;
+    }
+    Ok(())
+}
+
+/// The number of elements in a JSON array body, or of keys in an object.
+fn count_elements(body: &str) -> Result<usize> {
+    let value: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("--count needs a JSON body: {}", e))?;
+    match value {
+        serde_json::Value::Array(items) => Ok(items.len()),
+        serde_json::Value::Object(map) => Ok(map.len()),
+        _ => Err("--count needs a JSON array or object, got a single value".into()),
+    }
+}
+
+/// Fails the command when the response breaks an `--expect-*` option.
+fn check_expectations(resp: &Captured, opts: &Opts) -> Result<()> {
+    check_empty(resp.decoded()?.0.len() as u64, opts)?;
+    if !opts.assert.is_empty() {
+        assertion::run(&opts.assert, &resp.text()?)?;
+    }
+    if let Some(path) = &opts.expect_body_file {
+        fixture::check(&resp.text()?, path, opts.update_fixture)?;
+    }
     Ok(())
 }
 
-fn print_status(resp: &Response) -> Result<()> {
-    let status = format!("{:?} {}", resp.version(), resp.status()).blue();
-    println!("{}\n", status);
+fn check_empty(bytes: u64, opts: &Opts) -> Result<()> {
+    if opts.expect_empty && bytes > 0 {
+        return Err(format!("Expected an empty body, got {} bytes", bytes).into());
+    }
+    Ok(())
+}
+
+/// A Strict-Transport-Security header over plain http means the host
+/// wants HTTPS, even though clients ignore HSTS sent this way.
+fn warn_if_prefers_https(url: &Url, headers: &HeaderMap) {
+    if url.scheme() == "http" && headers.contains_key(header::STRICT_TRANSPORT_SECURITY) {
+        meta!(
+            "{} {} asks for HTTPS but was fetched over plain http",
+            "Warning:".yellow(),
+            url.host_str().unwrap_or_default()
+        );
+    }
+}
+
+/// Response headers show their tokens inline, but request headers are not
+/// otherwise printed, so each token gets a line naming its header.
+fn print_request_jwts(headers: &HeaderMap) {
+    for (name, value) in headers {
+        for token in jwt::find(value) {
+            meta!("{} {}", "JWT in request header".blue(), name);
+            jwt::print(&token);
+        }
+    }
+}
+
+/// Opens a pooled connection to the host with a cheap HEAD request, so the
+/// timed request does not pay for DNS, connect and TLS setup.
+async fn warmup(client: &HttpClient, url: &Url) {
+    let head = Request::new(reqwest::Method::HEAD, url.clone());
+    if let Err(e) = client.send(head).await {
+        meta!("{} {}", "Warmup failed:".yellow(), e);
+    }
+}
+
+async fn repeat_until(
+    client: &HttpClient,
+    req: Request,
+    m: BodyMatch<'_>,
+    opts: &Opts,
+) -> Result<Captured> {
+    for attempt in 1..=opts.max_attempts {
+        let attempt_req = req
+            .try_clone()
+            .ok_or("The request body cannot be re-sent")?;
+        let resp = Captured::fetch(client, attempt_req).await?;
+
+        let progress = format!("[{}/{}]", attempt, opts.max_attempts).dimmed();
+        let body = resp.text()?;
+        match m.find(&body) {
+            Some(found) => {
+                meta!("{} {} matched: {}\n", progress, resp.status, found.green());
+                return Ok(resp);
+            }
+            None => meta!("{} {} {}", progress, resp.status, "no match".yellow()),
+        }
+        if attempt < opts.max_attempts {
+            tokio::time::sleep(opts.interval).await;
+        }
+    }
+    Err(format!("No matching response after {} attempts", opts.max_attempts).into())
+}
+
+/// Echoes the request id and whether the server reflected it back.
+fn print_request_id(id: &str, headers: &HeaderMap) {
+    let echoed = match headers.get(REQUEST_ID).map(|v| v.to_str()) {
+        Some(Ok(v)) if v == id => "matches response".green(),
+        Some(Ok(v)) => format!("response has {}", v).red(),
+        Some(Err(_)) => "response has a non-text id".red(),
+        None => "not echoed by server".dimmed(),
+    };
+    meta!("{}: {} ({})\n", REQUEST_ID.yellow(), id, echoed);
+}
+
+fn print_timings(t: &Timings) {
+    let ms = |v: Option<f64>| v.map_or("-".into(), |v| format!("{:.1} ms", v));
+    meta!(
+        "\n{} time to headers {}, total {}",
+        "Timing:".yellow(),
+        ms(t.ttfb),
+        ms(t.total)
+    );
+}
+
+fn print_resp(resp: &Captured, opts: &Opts) -> Result<()> {
+    print_status(resp.version, resp.status)?;
+    print_headers(&resp.headers, opts)?;
+    // A blank line would be indistinguishable from a body of whitespace.
+    if resp.decoded()?.0.is_empty() {
+        if !opts.empty_ok {
+            meta!("{}", empty_body_note(resp.status).dimmed());
+        }
+        return Ok(());
+    }
+    let m = resp.mime();
+    if opts.grpc_web || m.as_ref().is_some_and(grpc_web::is_grpc_web) {
+        // Unrecognized framing falls through to the usual body output.
+        if let Some(frames) = grpc_web::frames(resp.decoded()?.0) {
+            print!("{}", grpc_web::render(&frames));
+            return Ok(());
+        }
+    }
+    let grep = opts.grep.as_ref().map(|pattern| Grep {
+        pattern,
+        context: opts.grep_context,
+    });
+    print_body(m, &resp.text()?, grep.as_ref(), opts.depth)
+}
+
+fn print_trailers(trailers: &HeaderMap, opts: &Opts) -> Result<()> {
+    meta!();
+    if trailers.is_empty() {
+        meta!("{}", "(no trailers)".dimmed());
+        return Ok(());
+    }
+    meta!("{}", "Trailers:".blue());
+    print_headers(trailers, opts)
+}
+
+/// 204 and 304 responses never have a body, so saying so is more useful
+/// than pointing out that it is empty.
+fn empty_body_note(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED => "(no content)",
+        _ => "(empty body)",
+    }
+}
+
+/// Colors a status by class: success, redirection, or error.
+fn paint_status(status: StatusCode) -> ColoredString {
+    let text = status.to_string();
+    if status.is_success() {
+        text.green()
+    } else if status.is_redirection() {
+        text.yellow()
+    } else {
+        text.red()
+    }
+}
+
+fn print_status(version: Version, status: StatusCode) -> Result<()> {
+    let status = format!("{:?} {}", version, status).blue();
+    meta!("{}\n", status);
     Ok(())
 }
 
-fn print_headers(resp: &Response) -> Result<()> {
-    for (name, value) in resp.headers() {
-        println!("{}: {:?}", name.to_string().green(), value);
+/// Prints each header value on its own line. `--raw-headers` prints the bytes as
+/// received instead of quoting them, and skips wrapping. Either way hyper
+/// has already lowercased the names and grouped repeated headers by name.
+fn print_headers(headers: &HeaderMap, opts: &Opts) -> Result<()> {
+    for (name, value) in headers {
+        match opts.raw_headers {
+            true => meta!(
+                "{}: {}",
+                name.to_string().green(),
+                String::from_utf8_lossy(value.as_bytes())
+            ),
+            false => {
+                let line = format!("{}: {:?}", name.to_string().green(), value);
+                meta!("{}", output::wrap(&line));
+            }
+        }
+        if opts.decode_jwt {
+            jwt::find(value).iter().for_each(jwt::print);
+        }
     }
-    print!("\n");
+    meta!();
     Ok(())
 }
 
-fn print_body(m: Option<Mime>, body: &String) -> Result<()> {
-    match m {
-        Some(v) if v == mime::APPLICATION_JSON => syntect_print(jsonxf::pretty_print(body)?),
-        _ => {
-            println!("{}", body);
-            Ok(())
+fn print_body(
+    m: Option<Mime>,
+    body: &str,
+    grep: Option<&Grep>,
+    depth: Option<usize>,
+) -> Result<()> {
+    match (m.as_ref().and_then(syntax_extension), depth) {
+        (Some("json"), Some(depth)) => {
+            let text = collapse::pretty_print(serde_json::from_str(body)?, depth)?;
+            syntect_print(text, "json", grep)
+        }
+        (Some("json"), None) => syntect_print(jsonxf::pretty_print(body)?, "json", grep),
+        (Some(ext), _) => syntect_print(body.to_string(), ext, grep),
+        (None, _) => {
+            let lines: Vec<&str> = LinesWithEndings::from(body).collect();
+            let color = grep.filter(|_| io::stdout().is_terminal());
+            print_lines(&lines, grep, |line| {
+                Ok(color.map_or_else(|| line.to_string(), |g| g.emphasize_plain(line)))
+            })
         }
     }
 }
 
-fn syntect_print(s: String) -> Result<()> {
+/// Prints the rendered lines, keeping only those `grep` selects, with `--`
+/// between groups that are not adjacent. Every line is rendered, selected
+/// or not, so that stateful highlighters see the whole body.
+fn print_lines<F>(lines: &[&str], grep: Option<&Grep>, mut render: F) -> Result<()>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let keep = grep.map(|g| g.select(lines));
+    let mut skipped = false;
+    let mut last: Option<String> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let rendered = render(line)?;
+        if keep.as_ref().is_some_and(|k| !k[i]) {
+            skipped = true;
+            continue;
+        }
+        if skipped && last.is_some() {
+            println!("{}", "--".dimmed());
+        }
+        skipped = false;
+        print!("{}", output::wrap(&rendered));
+        last = Some(rendered);
+    }
+    match last {
+        Some(line) if line.ends_with('\n') => {}
+        Some(_) => println!(),
+        None if grep.is_some() => meta!("{}", "(no lines match)".dimmed()),
+        None => println!(),
+    }
+    Ok(())
+}
+
+/// Maps a content type to the file extension syntect knows its syntax by.
+fn syntax_extension(m: &Mime) -> Option<&'static str> {
+    let suffix = m.suffix().map(|s| s.as_str());
+    match (m.subtype().as_str(), suffix) {
+        ("json", _) | (_, Some("json")) => Some("json"),
+        ("html", _) => Some("html"),
+        ("xml", _) | (_, Some("xml")) => Some("xml"),
+        ("yaml" | "x-yaml", _) => Some("yaml"),
+        ("toml", _) => Some("toml"),
+        _ => None,
+    }
+}
+
+/// Looks up a syntax by extension, degrading to plain text when the syntax
+/// set does not ship one.
+fn find_syntax<'a>(ps: &'a SyntaxSet, ext: &str) -> &'a SyntaxReference {
+    ps.find_syntax_by_extension(ext)
+        .unwrap_or_else(|| ps.find_syntax_plain_text())
+}
+
+fn syntect_print(s: String, ext: &str, grep: Option<&Grep>) -> Result<()> {
+    let lines: Vec<&str> = LinesWithEndings::from(&s).collect();
+    // Escape codes would corrupt a body redirected into a file.
+    if !io::stdout().is_terminal() {
+        return print_lines(&lines, grep, |line| Ok(line.to_string()));
+    }
+
     // Load these once at the start of your program
     let ps = SyntaxSet::load_defaults_newlines();
     let ts = ThemeSet::load_defaults();
 
-    let syntax = ps.find_syntax_by_extension("json").unwrap();
+    let syntax = find_syntax(&ps, ext);
     let mut h = HighlightLines::new(syntax, &ts.themes["base16-ocean.dark"]);
-    for line in LinesWithEndings::from(&s) {
-        let ranges: Vec<(Style, &str)> = h.highlight_line(line, &ps).unwrap();
-        let escaped = as_24_bit_terminal_escaped(&ranges[..], true);
-        print!("{}", escaped);
-    }
-    print!("\n");
-    Ok(())
+    print_lines(&lines, grep, |line| {
+        let mut ranges: Vec<(Style, &str)> = h.highlight_line(line, &ps)?;
+        if let Some(grep) = grep {
+            ranges = grep.emphasize(&ranges);
+        }
+        Ok(as_24_bit_terminal_escaped(&ranges[..], true))
+    })
 }
 
-fn get_content_type(resp: &Response) -> Option<Mime> {
-    let header = resp.headers().get(header::CONTENT_TYPE).map(|v| v.to_str());
+fn get_content_type(headers: &HeaderMap) -> Option<Mime> {
+    let header = headers.get(header::CONTENT_TYPE).map(|v| v.to_str());
     match header {
         Some(Ok(v)) => v.parse().ok(),
         _ => None,
@@ -93,6 +776,160 @@ mod tests {
         assert!(parse_url("https://httpbin.org/post").is_ok());
     }
 
+    #[test]
+    fn test_encode_path() {
+        use super::encode_path;
+        assert_eq!(
+            encode_path("https://example.com/my file.txt"),
+            "https://example.com/my%20file.txt"
+        );
+        assert_eq!(
+            encode_path("https://example.com/café?q=a b#x y"),
+            "https://example.com/caf%C3%A9?q=a b#x y"
+        );
+        assert_eq!(
+            encode_path("https://example.com/100%done"),
+            "https://example.com/100%25done"
+        );
+        assert_eq!(
+            encode_path("https://example.com/a%20b"),
+            "https://example.com/a%20b"
+        );
+        assert_eq!(encode_path("https://example.com"), "https://example.com");
+        assert_eq!(encode_path("abc"), "abc");
+    }
+
+    #[test]
+    fn test_encode_component() {
+        use super::encode_component;
+        assert_eq!(encode_component("a b/~c"), "a%20b%2F~c");
+        assert_eq!(encode_component("café"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn test_parse_secs() {
+        use super::parse_secs;
+        use std::time::Duration;
+        assert_eq!(parse_secs("1.5").unwrap(), Duration::from_millis(1500));
+        assert!(parse_secs("-1").is_err());
+        assert!(parse_secs("soon").is_err());
+    }
+
+    #[test]
+    fn test_text_strips_bom() {
+        use super::Captured;
+        use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+        use reqwest::StatusCode;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/json; charset=latin1"),
+        );
+        let resp = Captured::for_test(StatusCode::OK, headers, b"\xef\xbb\xbf{\"a\":\"\xc3\xa9\"}");
+        assert_eq!(resp.text().unwrap(), r#"{"a":"é"}"#);
+    }
+
+    #[test]
+    fn test_decoded_is_read_once() {
+        use super::Captured;
+        use flate2::{write::GzEncoder, Compression};
+        use reqwest::header::{HeaderMap, HeaderValue, CONTENT_ENCODING};
+        use reqwest::StatusCode;
+        use std::io::Write;
+        let gzip = |body: &[u8]| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body).unwrap();
+            encoder.finish().unwrap()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        let empty = Captured::for_test(StatusCode::OK, headers.clone(), &gzip(b""));
+        assert!(!empty.body.is_empty());
+        assert!(empty.decoded().unwrap().0.is_empty());
+
+        let resp = Captured::for_test(StatusCode::OK, headers, &gzip(b"hello"));
+        let (first, compression) = resp.decoded().unwrap();
+        assert_eq!(
+            (first, compression.map(|c| c.encoding.as_str())),
+            (&b"hello"[..], Some("gzip"))
+        );
+        assert!(std::ptr::eq(first, resp.decoded().unwrap().0));
+    }
+
+    #[test]
+    fn test_empty_body_note() {
+        use super::empty_body_note;
+        use reqwest::StatusCode;
+        assert_eq!(empty_body_note(StatusCode::NO_CONTENT), "(no content)");
+        assert_eq!(empty_body_note(StatusCode::NOT_MODIFIED), "(no content)");
+        assert_eq!(empty_body_note(StatusCode::OK), "(empty body)");
+    }
+
+    #[test]
+    fn test_parse_accept_language() {
+        use super::parse_accept_language;
+        assert_eq!(
+            parse_accept_language("en-US,fr ; q=0.8,*;q=0.10").unwrap(),
+            "en-US, fr;q=0.8, *;q=0.1"
+        );
+        assert!(parse_accept_language("en_US").is_err());
+        assert!(parse_accept_language("en;q=2").is_err());
+        assert!(parse_accept_language("en,").is_err());
+    }
+
+    #[test]
+    fn test_parse_bind() {
+        use super::parse_bind;
+        assert_eq!(parse_bind("10.0.0.2").unwrap().to_string(), "10.0.0.2");
+        assert_eq!(parse_bind("::1").unwrap().to_string(), "::1");
+        assert!(parse_bind("10.0.0.2:8080").is_err());
+        assert!(parse_bind("[::1]:8080").is_err());
+        assert!(parse_bind("eth0").is_err());
+    }
+
+    #[test]
+    fn test_count_elements() {
+        use super::count_elements;
+        assert_eq!(count_elements("[1, 2, 3]").unwrap(), 3);
+        assert_eq!(count_elements(r#"{"a": 1, "b": []}"#).unwrap(), 2);
+        assert!(count_elements("42").is_err());
+        assert!(count_elements("<html>").is_err());
+    }
+
+    #[test]
+    fn test_body_match() {
+        use super::BodyMatch;
+        use regex::Regex;
+        let body = r#"{"status": "done"}"#;
+        assert_eq!(BodyMatch::Contains("done").find(body), Some("done"));
+        assert_eq!(BodyMatch::Contains("pending").find(body), None);
+        let re = Regex::new(r#""status": "(\w+)""#).unwrap();
+        assert_eq!(BodyMatch::Regex(&re).find(body), Some("done"));
+    }
+
+    #[test]
+    fn test_syntax_extension() {
+        use super::syntax_extension;
+        let ext = |s: &str| syntax_extension(&s.parse().unwrap());
+        assert_eq!(ext("application/json; charset=utf-8"), Some("json"));
+        assert_eq!(ext("application/problem+json"), Some("json"));
+        assert_eq!(ext("text/html"), Some("html"));
+        assert_eq!(ext("application/atom+xml"), Some("xml"));
+        assert_eq!(ext("application/x-yaml"), Some("yaml"));
+        assert_eq!(ext("application/toml"), Some("toml"));
+        assert_eq!(ext("text/plain"), None);
+    }
+
+    #[test]
+    fn test_find_syntax_falls_back_to_plain_text() {
+        use super::find_syntax;
+        use syntect::parsing::SyntaxSet;
+        let ps = SyntaxSet::load_defaults_newlines();
+        assert_eq!(find_syntax(&ps, "json").name, "JSON");
+        assert_eq!(find_syntax(&ps, "no-such-syntax").name, "Plain Text");
+    }
+
     #[test]
     fn test_pretty_print_unwrap() {
         // assert_eq!(
@@ -0,0 +1,134 @@
+use super::json_output::body_value;
+use super::Captured;
+use crate::Result;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::Request;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The parts of a request that end up in an OpenAPI example, taken before
+/// the request is sent.
+pub struct RequestExample {
+    method: String,
+    path: String,
+    content_type: String,
+    body: Option<Vec<u8>>,
+}
+
+/// An OpenAPI 3 operation, keyed under its path and method when printed.
+#[derive(Serialize)]
+struct Operation {
+    #[serde(rename = "requestBody", skip_serializing_if = "Option::is_none")]
+    request_body: Option<Body>,
+    responses: BTreeMap<String, Response>,
+}
+
+#[derive(Serialize)]
+struct Body {
+    content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Serialize)]
+struct Response {
+    description: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Serialize)]
+struct MediaType {
+    example: Value,
+}
+
+impl RequestExample {
+    /// Streamed bodies, such as multipart uploads, have no example.
+    pub fn capture(req: &Request) -> Self {
+        Self {
+            method: req.method().as_str().to_lowercase(),
+            path: req.url().path().to_string(),
+            content_type: media_type(req.headers()),
+            body: req.body().and_then(|b| b.as_bytes()).map(<[u8]>::to_vec),
+        }
+    }
+}
+
+/// Prints the request and response as a fragment of an OpenAPI `paths`
+/// object.
+pub fn print(req: &RequestExample, resp: &Captured) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&fragment(req, resp)?)?);
+    Ok(())
+}
+
+fn fragment(req: &RequestExample, resp: &Captured) -> Result<Value> {
+    let request_body = req.body.as_ref().map(|body| Body {
+        content: example(
+            &req.content_type,
+            String::from_utf8_lossy(body).into_owned(),
+        ),
+    });
+    let response = Response {
+        description: resp
+            .status
+            .canonical_reason()
+            .unwrap_or_default()
+            .to_string(),
+        content: match resp.decoded()?.0.is_empty() {
+            true => BTreeMap::new(),
+            false => example(&media_type(&resp.headers), resp.text()?),
+        },
+    };
+    let operation = Operation {
+        request_body,
+        responses: BTreeMap::from([(resp.status.as_str().to_string(), response)]),
+    };
+    Ok(serde_json::json!({ &req.path: { &req.method: operation } }))
+}
+
+fn example(media_type: &str, body: String) -> BTreeMap<String, MediaType> {
+    let example = body_value(body);
+    BTreeMap::from([(media_type.to_string(), MediaType { example })])
+}
+
+/// The content type without parameters such as `charset`.
+fn media_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|| "application/octet-stream".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::{StatusCode, Url};
+
+    #[test]
+    fn test_fragment() {
+        let req = reqwest::Client::new()
+            .post("http://localhost/users?x=1")
+            .json(&serde_json::json!({"name": "ann"}))
+            .build()
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/json; charset=utf-8"),
+        );
+        let mut resp = Captured::for_test(StatusCode::CREATED, headers, br#"{"id":1}"#);
+        resp.received.url = Url::parse("http://localhost/users").unwrap();
+        assert_eq!(
+            fragment(&RequestExample::capture(&req), &resp).unwrap(),
+            serde_json::json!({"/users": {"post": {
+                "requestBody": {"content": {"application/json": {"example": {"name": "ann"}}}},
+                "responses": {"201": {
+                    "description": "Created",
+                    "content": {"application/json": {"example": {"id": 1}}}
+                }}
+            }}})
+        );
+    }
+}
@@ -1,54 +1,309 @@
-use super::parse_url;
-use super::print_resp;
+use super::client::HttpClient;
+use super::last;
+use super::send;
+use super::{resolve_url, PathParam};
 use crate::Error;
+use crate::Opts;
 use crate::Result;
 use clap::Args;
-use reqwest::Client;
-use std::collections::HashMap;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::multipart::{Form, Part};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+const JSON: &str = "application/json";
+const DEFAULT_MAX_FIELDS: usize = 1000;
+
 #[derive(Args, Debug)]
 pub struct Post {
-    #[arg(value_parser = parse_url)]
-    url: String,
+    /// The URL, which may contain {name} placeholders for --path.
+    pub url: String,
+    /// Fill the {name} placeholder in the URL with a percent-encoded value.
+    #[arg(long = "path", value_name = "NAME=VALUE")]
+    pub path_params: Vec<PathParam>,
     /// Set the request body.
     ///     params:
-    ///         key1=value1
+    ///         key1=value1       string field
+    ///         key2:=[1, 2]      raw JSON value (yes/on/no/off become booleans)
+    ///         file@photo.png    file upload (multipart only)
+    ///         meta:=@data.json  JSON file
+    ///     File paths may end in ;type=<mime> to set the part's content type.
+    ///     A value of '$LAST' or '$LAST.some.path' takes that part of the
+    ///     last JSON response. Quote it so the shell leaves it alone.
     #[arg(value_parser = parse_kv_pair)]
-    body: Vec<KvPair>,
+    pub body: Vec<KvPair>,
+    /// Send the last response (@last) or a file (@FILE) as the body, as is.
+    #[arg(
+        long = "body",
+        value_name = "@last|@FILE",
+        conflicts_with_all = ["body", "multipart", "body_yaml", "json_body"]
+    )]
+    pub body_source: Option<BodySource>,
+    /// Send the fields as multipart/form-data instead of JSON.
+    #[arg(long)]
+    pub multipart: bool,
+    /// Send this YAML file, converted to JSON, as the body.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["body", "multipart"])]
+    pub body_yaml: Option<PathBuf>,
+    /// Send this complete JSON document as the body.
+    #[arg(
+        long,
+        value_name = "JSON",
+        value_parser = parse_json_body,
+        conflicts_with_all = ["body", "multipart", "body_yaml"]
+    )]
+    pub json_body: Option<Value>,
+    /// Require := values to be exact JSON, without reading yes/on/no/off
+    /// as booleans.
+    #[arg(long)]
+    pub strict_json: bool,
+    /// Refuse to send more than this many fields, to catch a shell glob
+    /// expanding into far more arguments than intended.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_FIELDS)]
+    pub max_fields: usize,
+}
+
+/// How the value of a body field is interpreted, chosen by its separator.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    /// `key=value`
+    Text,
+    /// `key:=value`
+    Json,
+    /// `key@path`
+    File,
+    /// `key:=@path`
+    JsonFile,
+}
+
+/// Separators in the order they are tried at each position, longest first.
+const SEPARATORS: [(&str, Kind); 4] = [
+    (":=@", Kind::JsonFile),
+    (":=", Kind::Json),
+    ("=", Kind::Text),
+    ("@", Kind::File),
+];
+
+/// Where `--body` reads the raw body from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BodySource {
+    Last,
+    File(PathBuf),
+}
+
+impl FromStr for BodySource {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.strip_prefix('@') {
+            Some("last") => Ok(BodySource::Last),
+            Some(path) if !path.is_empty() => Ok(BodySource::File(path.into())),
+            _ => Err(format!("Expected @last or @FILE, got {:?}", s).into()),
+        }
+    }
+}
+
+impl BodySource {
+    /// The bytes and their content type: JSON when they parse as JSON,
+    /// otherwise guessed from the file name.
+    fn read(&self) -> Result<(Vec<u8>, String)> {
+        let (data, guess) = match self {
+            BodySource::Last => (last::load()?, mime::APPLICATION_OCTET_STREAM),
+            BodySource::File(path) => (
+                fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+                mime_guess::from_path(path).first_or_octet_stream(),
+            ),
+        };
+        let mime = match serde_json::from_slice::<Value>(&data) {
+            Ok(_) => JSON.to_string(),
+            Err(_) => guess.to_string(),
+        };
+        Ok((data, mime))
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct KvPair {
     pub key: String,
     pub value: String,
+    pub kind: Kind,
 }
 
 impl FromStr for KvPair {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self> {
-        let mut parts = s.split("=");
-        let err = || format!("Failed to parse {}", s);
+        // The earliest separator wins, so values may contain any of them.
+        for (i, _) in s.char_indices() {
+            for (sep, kind) in SEPARATORS {
+                if s[i..].starts_with(sep) {
+                    return Ok(Self {
+                        key: s[..i].to_string(),
+                        value: s[i + sep.len()..].to_string(),
+                        kind,
+                    });
+                }
+            }
+        }
+        Err(format!("Failed to parse {}", s).into())
+    }
+}
+
+impl KvPair {
+    /// Splits a file value into its path and the `;type=` override, if any.
+    fn path_and_type(&self) -> (&str, Option<&str>) {
+        match self.value.rsplit_once(";type=") {
+            Some((path, mime)) => (path, Some(mime)),
+            None => (&self.value, None),
+        }
+    }
+
+    /// Parses a `:=` value. Unless `strict`, a value that is not JSON but
+    /// reads as a yes/no answer (`yes`, `on`, `no`, `off`, in any case)
+    /// becomes a boolean.
+    fn json(&self, strict: bool) -> Result<Value> {
+        match (serde_json::from_str(&self.value), strict) {
+            (Ok(value), _) => Ok(value),
+            (Err(e), false) => coerce_bool(&self.value).ok_or_else(|| invalid_json(&self.key, e)),
+            (Err(e), true) => Err(invalid_json(&self.key, e)),
+        }
+    }
 
-        Ok(Self {
-            key: parts.next().ok_or_else(err)?.to_string(),
-            value: parts.next().ok_or_else(err)?.to_string(),
-        })
+    /// The value of a `$LAST` reference, kept as JSON for `:=` fields and
+    /// made a string for `=` fields. `None` for any other value.
+    fn last(&self) -> Option<Result<Value>> {
+        if !matches!(self.kind, Kind::Text | Kind::Json) {
+            return None;
+        }
+        let path = last::reference(&self.value)?;
+        Some(last::value(path).map(|value| match (self.kind, value) {
+            (Kind::Text, Value::String(s)) => Value::String(s),
+            (Kind::Text, value) => Value::String(value.to_string()),
+            (_, value) => value,
+        }))
+    }
+
+    fn json_file(&self) -> Result<Value> {
+        let (path, _) = self.path_and_type();
+        let data =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Invalid JSON in {}: {}", path, e).into())
+    }
+}
+
+fn coerce_bool(s: &str) -> Option<Value> {
+    match s.to_ascii_lowercase().as_str() {
+        "yes" | "on" => Some(Value::Bool(true)),
+        "no" | "off" => Some(Value::Bool(false)),
+        _ => None,
     }
 }
 
+fn invalid_json(key: &str, e: serde_json::Error) -> Error {
+    format!("Invalid JSON for {}: {}", key, e).into()
+}
+
 pub fn parse_kv_pair(s: &str) -> Result<KvPair> {
-    Ok(s.parse()?)
+    s.parse()
+}
+
+/// The error names the line and column of the syntax error.
+fn parse_json_body(s: &str) -> Result<Value> {
+    serde_json::from_str(s).map_err(|e| format!("Invalid JSON: {}", e).into())
+}
+
+pub async fn post(client: &HttpClient, args: &Post, opts: &Opts) -> Result<()> {
+    check_field_count(&args.body, args.max_fields)?;
+    let req = client.post(resolve_url(&args.url, &args.path_params, opts)?);
+    if let Some(source) = &args.body_source {
+        let (data, mime) = source.read()?;
+        return send(client, req.header(CONTENT_TYPE, mime).body(data), opts).await;
+    }
+    let req = match (&args.json_body, &args.body_yaml, args.multipart) {
+        (Some(value), _, _) => req.json(value),
+        (None, Some(path), _) => req.json(&yaml_body(path)?),
+        (None, None, true) => req.multipart(multipart_body(&args.body, args.strict_json)?),
+        (None, None, false) => req.json(&json_body(&args.body, args.strict_json)?),
+    };
+    send(client, req, opts).await
+}
+
+fn check_field_count(pairs: &[KvPair], max: usize) -> Result<()> {
+    if pairs.len() > max {
+        return Err(format!(
+            "Found {} fields, more than --max-fields {}; raise it if this is intended",
+            pairs.len(),
+            max
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn json_body(pairs: &[KvPair], strict: bool) -> Result<Map<String, Value>> {
+    let mut body = Map::new();
+    for pair in pairs {
+        if let Some(value) = pair.last() {
+            body.insert(pair.key.clone(), value?);
+            continue;
+        }
+        let value = match pair.kind {
+            Kind::Text => Value::String(pair.value.clone()),
+            Kind::Json => pair.json(strict)?,
+            Kind::JsonFile => pair.json_file()?,
+            Kind::File => {
+                return Err(format!("Uploading {} needs --multipart", pair.value).into());
+            }
+        };
+        body.insert(pair.key.clone(), value);
+    }
+    Ok(body)
+}
+
+fn yaml_body(path: &Path) -> Result<Value> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_yaml(&data).map_err(|e| format!("Invalid YAML in {}: {}", path.display(), e).into())
+}
+
+/// Parses YAML into the JSON value it stands for. The error names the line
+/// and column of the problem.
+fn parse_yaml(data: &str) -> std::result::Result<Value, serde_yaml::Error> {
+    serde_yaml::from_str(data)
 }
 
-pub async fn post(client: Client, args: &Post) -> Result<()> {
-    let mut body = HashMap::new();
-    for pair in args.body.iter() {
-        body.insert(&pair.key, &pair.value);
+fn multipart_body(pairs: &[KvPair], strict: bool) -> Result<Form> {
+    let mut form = Form::new();
+    for pair in pairs {
+        let part = match (pair.last().transpose()?, pair.kind) {
+            (Some(Value::String(s)), Kind::Text) => Part::text(s),
+            (Some(value), _) => Part::text(value.to_string()).mime_str(JSON)?,
+            (None, Kind::Text) => Part::text(pair.value.clone()),
+            (None, Kind::Json) => Part::text(pair.json(strict)?.to_string()).mime_str(JSON)?,
+            (None, Kind::File | Kind::JsonFile) => file_part(pair)?,
+        };
+        form = form.part(pair.key.clone(), part);
     }
+    Ok(form)
+}
 
-    let resp = client.post(&args.url).json(&body).send().await?;
-    Ok(print_resp(resp).await?)
+/// Attaches a file, typed by `;type=`, by being a JSON field, or by its
+/// extension, in that order.
+fn file_part(pair: &KvPair) -> Result<Part> {
+    let (path, mime) = pair.path_and_type();
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mime = match (mime, pair.kind) {
+        (Some(mime), _) => mime.to_string(),
+        (None, Kind::JsonFile) => JSON.to_string(),
+        (None, _) => mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string(),
+    };
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Part::bytes(data).file_name(name).mime_str(&mime)?)
 }
 
 #[cfg(test)]
@@ -62,15 +317,126 @@ mod tests {
             parse_kv_pair("a=1").unwrap(),
             KvPair {
                 key: "a".into(),
-                value: "1".into()
+                value: "1".into(),
+                kind: Kind::Text,
             }
         );
         assert_eq!(
             parse_kv_pair("b=").unwrap(),
             KvPair {
                 key: "b".into(),
-                value: "".into()
+                value: "".into(),
+                kind: Kind::Text,
             }
         );
     }
+
+    #[test]
+    fn test_parse_kv_pair_separators() {
+        let kind = |s: &str| {
+            let pair = parse_kv_pair(s).unwrap();
+            (pair.key, pair.value, pair.kind)
+        };
+        assert_eq!(kind("n:=1"), ("n".into(), "1".into(), Kind::Json));
+        assert_eq!(kind("f@a.png"), ("f".into(), "a.png".into(), Kind::File));
+        assert_eq!(
+            kind("meta:=@data.json;type=application/json"),
+            (
+                "meta".into(),
+                "data.json;type=application/json".into(),
+                Kind::JsonFile
+            )
+        );
+        assert_eq!(
+            kind("url=a=b@c"),
+            ("url".into(), "a=b@c".into(), Kind::Text)
+        );
+    }
+
+    #[test]
+    fn test_path_and_type() {
+        let pair = parse_kv_pair("meta:=@data.json;type=application/vnd.x+json").unwrap();
+        assert_eq!(
+            pair.path_and_type(),
+            ("data.json", Some("application/vnd.x+json"))
+        );
+        assert_eq!(
+            parse_kv_pair("f@a.png").unwrap().path_and_type(),
+            ("a.png", None)
+        );
+    }
+
+    #[test]
+    fn test_json_body() {
+        let pairs: Vec<KvPair> = ["a=1", "b:=[1,2]"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let body = json_body(&pairs, false).unwrap();
+        assert_eq!(
+            Value::Object(body),
+            serde_json::json!({"a": "1", "b": [1, 2]})
+        );
+        assert!(json_body(&[parse_kv_pair("f@a.png").unwrap()], false).is_err());
+        assert!(json_body(&[parse_kv_pair("n:=nope").unwrap()], false).is_err());
+    }
+
+    #[test]
+    fn test_bool_coercion() {
+        let pairs: Vec<KvPair> = ["a:=yes", "b:=OFF", "c:=true"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let body = json_body(&pairs, false).unwrap();
+        assert_eq!(
+            Value::Object(body),
+            serde_json::json!({"a": true, "b": false, "c": true})
+        );
+        assert!(json_body(&pairs, true).is_err());
+        assert!(json_body(&pairs[2..], true).is_ok());
+    }
+
+    #[test]
+    fn test_check_field_count() {
+        let pairs: Vec<KvPair> = ["a=1", "b=2", "c=3"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert!(check_field_count(&pairs, 3).is_ok());
+        let err = check_field_count(&pairs, 2).unwrap_err();
+        assert!(err.to_string().starts_with("Found 3 fields"));
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let value = parse_yaml("name: ann\ntags:\n  - a\n  - b\nage: 3\n").unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"name": "ann", "tags": ["a", "b"], "age": 3})
+        );
+        let err = parse_yaml("a: 1\nb: [2\n").unwrap_err();
+        assert!(err.location().is_some());
+        assert!(err.to_string().contains("line"));
+    }
+
+    #[test]
+    fn test_parse_json_body() {
+        assert_eq!(
+            parse_json_body(r#"{"a":1,"nested":{"b":2}}"#).unwrap(),
+            serde_json::json!({"a": 1, "nested": {"b": 2}})
+        );
+        let err = parse_json_body(r#"{"a":}"#).unwrap_err();
+        assert!(err.to_string().ends_with("at line 1 column 6"));
+    }
+
+    #[test]
+    fn test_parse_body_source() {
+        assert_eq!("@last".parse::<BodySource>().unwrap(), BodySource::Last);
+        assert_eq!(
+            "@data.bin".parse::<BodySource>().unwrap(),
+            BodySource::File("data.bin".into())
+        );
+        assert!("last".parse::<BodySource>().is_err());
+        assert!("@".parse::<BodySource>().is_err());
+    }
 }
@@ -0,0 +1,102 @@
+use super::client::HttpClient;
+use super::{paint_status, parse_secs};
+use crate::{Error, Result};
+use clap::Args;
+use colored::*;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+#[derive(Args, Debug)]
+pub struct Probe {
+    /// The host to probe, without a scheme.
+    pub host: String,
+    /// Extra ports to try with both http and https.
+    #[arg(long, value_delimiter = ',', default_values_t = [8000, 8080, 8443])]
+    pub ports: Vec<u16>,
+    /// Seconds to wait for each attempt.
+    #[arg(long, value_name = "SECS", default_value = "3", value_parser = parse_secs)]
+    pub timeout: Duration,
+}
+
+/// How one candidate URL answered.
+enum Outcome {
+    Status(reqwest::StatusCode),
+    Failed(String),
+}
+
+pub async fn probe(client: &HttpClient, args: &Probe) -> Result<()> {
+    let urls = candidates(&args.host, &args.ports);
+    let mut attempts = JoinSet::new();
+    for (i, url) in urls.iter().enumerate() {
+        let client = client.clone();
+        let req = client.get(url).timeout(args.timeout);
+        attempts.spawn(async move {
+            let start = Instant::now();
+            // Report what answers at this exact URL rather than where it
+            // redirects to.
+            let outcome = match client.execute(req.build()?).await {
+                Ok(resp) => Outcome::Status(resp.status()),
+                Err(e) => Outcome::Failed(describe(&e)),
+            };
+            Ok::<_, Error>((i, outcome, start.elapsed()))
+        });
+    }
+
+    let mut results = Vec::with_capacity(urls.len());
+    while let Some(joined) = attempts.join_next().await {
+        results.push(joined??);
+    }
+    results.sort_by_key(|(i, _, _)| *i);
+
+    let width = urls.iter().map(|u| u.len()).max().unwrap_or_default();
+    for (i, outcome, elapsed) in results {
+        let outcome = match outcome {
+            Outcome::Status(s) => paint_status(s),
+            Outcome::Failed(e) => e.dimmed(),
+        };
+        println!(
+            "{:width$}  {:>7.1} ms  {}",
+            urls[i],
+            elapsed.as_secs_f64() * 1000.0,
+            outcome
+        );
+    }
+    Ok(())
+}
+
+/// The URLs tried for a host: the default ports first, then each extra port
+/// with both schemes.
+fn candidates(host: &str, ports: &[u16]) -> Vec<String> {
+    let mut urls = vec![format!("https://{}/", host), format!("http://{}/", host)];
+    for port in ports {
+        urls.push(format!("https://{}:{}/", host, port));
+        urls.push(format!("http://{}:{}/", host, port));
+    }
+    urls
+}
+
+fn describe(e: &Error) -> String {
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(e) if e.is_timeout() => "timed out".into(),
+        Some(e) if e.is_connect() => "no connection".into(),
+        _ => "error".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            candidates("example.com", &[8080]),
+            [
+                "https://example.com/",
+                "http://example.com/",
+                "https://example.com:8080/",
+                "http://example.com:8080/",
+            ]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::Result;
+use colored::*;
+use reqwest::header::{
+    HeaderMap, HeaderName, AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE,
+    LOCATION, PROXY_AUTHORIZATION, TRANSFER_ENCODING, WWW_AUTHENTICATE,
+};
+use reqwest::{Method, Request, Response, StatusCode, Url};
+
+/// The number of redirects followed when `--max-redirects` is not given.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Decides how redirects are followed. reqwest's own following is turned
+/// off so that each hop is built here, where its method, body and headers
+/// can be chosen. By default this mirrors reqwest: 301, 302 and 303 turn
+/// the request into a body-less GET, while 307 and 308 replay it as is.
+#[derive(Clone, Debug)]
+pub struct RedirectPolicy {
+    pub max: usize,
+    /// Replay the original method and body on 301, 302 and 303 too. This
+    /// deviates from RFC 9110, which lets clients switch to GET, but some
+    /// APIs redirect a POST and expect it to be sent again unchanged.
+    pub keep_method: bool,
+    /// Fail on a hop from https to http instead of warning about it.
+    pub refuse_downgrade: bool,
+    /// Keep credentials on hops to another host, which is only safe when
+    /// every host in the chain is trusted.
+    pub allow_cross_origin_auth: bool,
+    /// Print each hop as it is followed. Off by default, so that only the
+    /// final response is shown.
+    pub trace: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max: DEFAULT_MAX_REDIRECTS,
+            keep_method: false,
+            refuse_downgrade: false,
+            allow_cross_origin_auth: false,
+            trace: false,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// Builds the request for the next hop, given the request that produced
+    /// `resp` and how many hops have been followed so far. Returns `None`
+    /// when `resp` is not a redirect worth following. A request whose body
+    /// could not be kept, as built by [`without_body`], is only followed
+    /// where the hop drops the body anyway.
+    pub fn next(
+        &self,
+        mut req: Request,
+        has_body: bool,
+        resp: &Response,
+        hops: usize,
+    ) -> Result<Option<Request>> {
+        let status = resp.status();
+        let rewrite = match status {
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER => {
+                !self.keep_method
+            }
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => false,
+            _ => return Ok(None),
+        };
+        if !rewrite && !has_body {
+            return Ok(None);
+        }
+        let Some(location) = resp.headers().get(LOCATION) else {
+            return Ok(None);
+        };
+        if hops >= self.max {
+            return Err(format!("Stopped after {} redirects", self.max).into());
+        }
+        let next = req.url().join(location.to_str()?)?;
+        if req.url().scheme() == "https" && next.scheme() == "http" {
+            if self.refuse_downgrade {
+                return Err(format!("Refusing to follow a redirect from https to {}", next).into());
+            }
+            meta!(
+                "{} redirect from https to {} is not encrypted",
+                "Warning:".yellow(),
+                next
+            );
+        }
+
+        if rewrite {
+            *req.body_mut() = None;
+            for name in [
+                TRANSFER_ENCODING,
+                CONTENT_ENCODING,
+                CONTENT_TYPE,
+                CONTENT_LENGTH,
+            ] {
+                req.headers_mut().remove(name);
+            }
+            if !matches!(*req.method(), Method::GET | Method::HEAD) {
+                *req.method_mut() = Method::GET;
+            }
+        }
+        // Cookies set along the way are added by the client's cookie store.
+        if is_cross_origin(req.url(), &next) && !self.allow_cross_origin_auth {
+            remove_credentials(req.headers_mut());
+        }
+        if self.trace {
+            meta!(
+                "{} {} {} → {}",
+                "Redirect".blue(),
+                status.as_u16().to_string().blue(),
+                req.url(),
+                next
+            );
+        }
+        *req.url_mut() = next;
+        Ok(Some(req))
+    }
+}
+
+/// A copy of `req` without its body, for requests whose streamed body
+/// cannot be cloned.
+pub fn without_body(req: &Request) -> Request {
+    let mut copy = Request::new(req.method().clone(), req.url().clone());
+    *copy.headers_mut() = req.headers().clone();
+    *copy.timeout_mut() = req.timeout().copied();
+    *copy.version_mut() = req.version();
+    copy
+}
+
+/// Whether a hop leaves the host (or port) credentials were meant for.
+fn is_cross_origin(from: &Url, to: &Url) -> bool {
+    from.host_str() != to.host_str() || from.port_or_known_default() != to.port_or_known_default()
+}
+
+fn remove_credentials(headers: &mut HeaderMap) {
+    for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, WWW_AUTHENTICATE] {
+        headers.remove(name);
+    }
+    headers.remove("cookie2");
+    // --netrc, --aws-sigv4 and the config file mark what they add.
+    let marked: Vec<HeaderName> = headers
+        .iter()
+        .filter(|(_, value)| value.is_sensitive())
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in marked {
+        headers.remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(status: u16, location: &str) -> Response {
+        http::Response::builder()
+            .status(status)
+            .header(LOCATION, location)
+            .body("")
+            .unwrap()
+            .into()
+    }
+
+    fn post(url: &str) -> Request {
+        let client = reqwest::Client::new();
+        client
+            .post(url)
+            .header(AUTHORIZATION, "Bearer t")
+            .body("{}")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_found_turns_post_into_get() {
+        let policy = RedirectPolicy::default();
+        let next = policy
+            .next(post("https://a.io/x"), true, &redirect(302, "/y"), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.method(), Method::GET);
+        assert_eq!(next.url().as_str(), "https://a.io/y");
+        assert!(next.body().is_none());
+        assert!(next.headers().contains_key(AUTHORIZATION));
+    }
+
+    #[test]
+    fn test_keep_method_replays_post() {
+        let policy = RedirectPolicy {
+            keep_method: true,
+            ..Default::default()
+        };
+        let next = policy
+            .next(post("https://a.io/x"), true, &redirect(301, "/y"), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.method(), Method::POST);
+        assert_eq!(next.body().and_then(|b| b.as_bytes()), Some(&b"{}"[..]));
+    }
+
+    #[test]
+    fn test_cross_origin_drops_credentials() {
+        let policy = RedirectPolicy::default();
+        let next = policy
+            .next(
+                post("https://a.io/x"),
+                true,
+                &redirect(307, "https://b.io/x"),
+                0,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.method(), Method::POST);
+        assert!(!next.headers().contains_key(AUTHORIZATION));
+    }
+
+    #[test]
+    fn test_cross_origin_drops_sensitive_headers() {
+        let mut req = post("https://a.io/x");
+        let mut key = reqwest::header::HeaderValue::from_static("k");
+        key.set_sensitive(true);
+        req.headers_mut().insert("x-api-key", key);
+        req.headers_mut().insert("x-trace", "t".parse().unwrap());
+        let to_b = || redirect(307, "https://b.io/x");
+
+        let next = RedirectPolicy::default()
+            .next(req.try_clone().unwrap(), true, &to_b(), 0)
+            .unwrap()
+            .unwrap();
+        assert!(!next.headers().contains_key("x-api-key"));
+        assert!(next.headers().contains_key("x-trace"));
+
+        let policy = RedirectPolicy {
+            allow_cross_origin_auth: true,
+            ..Default::default()
+        };
+        let next = policy.next(req, true, &to_b(), 0).unwrap().unwrap();
+        assert!(next.headers().contains_key(AUTHORIZATION));
+        assert!(next.headers().contains_key("x-api-key"));
+    }
+
+    #[test]
+    fn test_limits_and_non_redirects() {
+        let policy = RedirectPolicy {
+            max: 1,
+            ..Default::default()
+        };
+        assert!(policy
+            .next(post("https://a.io/"), true, &redirect(302, "/"), 1)
+            .is_err());
+        let ok: Response = http::Response::builder()
+            .status(200)
+            .body("")
+            .unwrap()
+            .into();
+        assert!(policy
+            .next(post("https://a.io/"), true, &ok, 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let to_http = || redirect(302, "http://a.io/y");
+        let next = RedirectPolicy::default()
+            .next(post("https://a.io/x"), true, &to_http(), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.url().as_str(), "http://a.io/y");
+        assert!(!next.headers().contains_key(AUTHORIZATION));
+
+        let policy = RedirectPolicy {
+            refuse_downgrade: true,
+            ..Default::default()
+        };
+        assert!(policy
+            .next(post("https://a.io/x"), true, &to_http(), 0)
+            .is_err());
+        assert!(policy
+            .next(
+                post("http://a.io/x"),
+                true,
+                &redirect(302, "https://a.io/y"),
+                0
+            )
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_streamed_body_follows_only_rewrites() {
+        let form = reqwest::multipart::Form::new().text("a", "b");
+        let req = reqwest::Client::new()
+            .post("https://a.io/upload")
+            .multipart(form)
+            .build()
+            .unwrap();
+        assert!(req.try_clone().is_none());
+        let policy = RedirectPolicy::default();
+        let next = policy
+            .next(without_body(&req), false, &redirect(303, "/done"), 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.method(), Method::GET);
+        assert_eq!(next.url().as_str(), "https://a.io/done");
+        assert!(!next.headers().contains_key(CONTENT_TYPE));
+        let resend = policy.next(without_body(&req), false, &redirect(307, "/again"), 0);
+        assert!(resend.unwrap().is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! AWS Signature Version 4 request signing.
+//!
+//! Follows <https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html>:
+//! the request is reduced to a canonical form, hashed into a string to sign,
+//! and signed with a key derived from the secret, date, region and service.
+
+use super::encode_component;
+use crate::{Error, Result};
+use hmac::{Hmac, KeyInit, Mac};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+/// Sent in place of the payload hash when the body is streamed. Only S3
+/// accepts it.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// The `region/service` pair a request is signed for.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scope {
+    pub region: String,
+    pub service: String,
+}
+
+impl FromStr for Scope {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('/') {
+            Some((region, service)) if !region.is_empty() && !service.is_empty() => Ok(Self {
+                region: region.to_string(),
+                service: service.to_string(),
+            }),
+            _ => Err(format!("Expected REGION/SERVICE, e.g. us-east-1/s3, got {:?}", s).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub access_key: String,
+    pub secret_key: String,
+    pub session_token: Option<String>,
+}
+
+/// Signs `req` in place at `time`, formatted as `YYYYMMDD'T'HHMMSS'Z'`.
+/// Only the headers already on the request are signed, so the client's
+/// default headers, added later, are left out.
+pub fn sign(req: &mut Request, scope: &Scope, creds: &Credentials, time: &str) -> Result<()> {
+    let s3 = scope.service == "s3";
+    let payload = match req.body() {
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => hex::encode(Sha256::digest(bytes)),
+            None if s3 => UNSIGNED_PAYLOAD.to_string(),
+            None => {
+                return Err(format!(
+                    "A streamed body, such as a multipart upload, cannot be signed for {}; \
+                     only s3 accepts an unsigned payload",
+                    scope.service
+                )
+                .into())
+            }
+        },
+        None => hex::encode(Sha256::digest(b"")),
+    };
+
+    let headers = req.headers_mut();
+    headers.insert("x-amz-date", HeaderValue::from_str(time)?);
+    if let Some(token) = &creds.session_token {
+        let mut value = HeaderValue::from_str(token)?;
+        value.set_sensitive(true);
+        headers.insert("x-amz-security-token", value);
+    }
+    if s3 {
+        headers.insert("x-amz-content-sha256", HeaderValue::from_str(&payload)?);
+    }
+
+    let (signed_headers, canonical_headers) = canonical_headers(req)?;
+    let canonical = [
+        req.method().as_str(),
+        &canonical_uri(req.url().path(), s3),
+        &canonical_query(req.url()),
+        &canonical_headers,
+        &signed_headers,
+        &payload,
+    ]
+    .join("\n");
+
+    let date = &time[..8];
+    let credential_scope = format!("{}/{}/{}/aws4_request", date, scope.region, scope.service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        time,
+        credential_scope,
+        hex::encode(Sha256::digest(canonical.as_bytes()))
+    );
+
+    let key = [
+        scope.region.as_bytes(),
+        scope.service.as_bytes(),
+        b"aws4_request",
+    ]
+    .iter()
+    .fold(
+        hmac(
+            format!("AWS4{}", creds.secret_key).as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac(&key, part),
+    );
+    let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+    let mut value = HeaderValue::try_from(format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        ALGORITHM, creds.access_key, credential_scope, signed_headers, signature
+    ))?;
+    value.set_sensitive(true);
+    req.headers_mut().insert(AUTHORIZATION, value);
+    Ok(())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The path with each segment encoded again, except for S3, which signs
+/// the path exactly as sent.
+fn canonical_uri(path: &str, s3: bool) -> String {
+    let path = if path.is_empty() { "/" } else { path };
+    match s3 {
+        true => path.to_string(),
+        false => path
+            .split('/')
+            .map(encode_component)
+            .collect::<Vec<_>>()
+            .join("/"),
+    }
+}
+
+/// Query parameters re-encoded the AWS way and sorted by name, then value.
+fn canonical_query(url: &reqwest::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (encode_component(&k), encode_component(&v)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Returns the `;`-joined signed header names and the canonical header
+/// block, which always includes `host`.
+fn canonical_headers(req: &Request) -> Result<(String, String)> {
+    let url = req.url();
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut headers = vec![("host".to_string(), host)];
+    for name in req.headers().keys() {
+        if name == AUTHORIZATION {
+            continue;
+        }
+        let values = req
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|v| v.to_str().map(normalize_space))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| format!("Cannot sign non-ASCII header {}", name))?;
+        headers.push((name.as_str().to_string(), values.join(",")));
+    }
+    headers.sort();
+
+    let signed = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let block = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    Ok((signed, block))
+}
+
+fn normalize_space(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_aws_example() {
+        // The IAM ListUsers example from the AWS SigV4 documentation.
+        let client = reqwest::Client::new();
+        let mut req = client
+            .get("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08")
+            .header(
+                "Content-Type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )
+            .build()
+            .unwrap();
+        let creds = Credentials {
+            access_key: "AKIDEXAMPLE".into(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+        };
+        let scope: Scope = "us-east-1/iam".parse().unwrap();
+        sign(&mut req, &scope, &creds, "20150830T123600Z").unwrap();
+        assert_eq!(
+            req.headers()[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn test_streamed_body_is_signed_only_for_s3() {
+        let creds = Credentials {
+            access_key: "AKIDEXAMPLE".into(),
+            secret_key: "secret".into(),
+            session_token: None,
+        };
+        let upload = || {
+            let form = reqwest::multipart::Form::new().text("a", "b");
+            let client = reqwest::Client::new();
+            client
+                .put("https://a.io/k")
+                .multipart(form)
+                .build()
+                .unwrap()
+        };
+        let mut req = upload();
+        sign(
+            &mut req,
+            &"us-east-1/s3".parse().unwrap(),
+            &creds,
+            "20150830T123600Z",
+        )
+        .unwrap();
+        assert_eq!(req.headers()["x-amz-content-sha256"], UNSIGNED_PAYLOAD);
+        let scope = "us-east-1/execute-api".parse().unwrap();
+        assert!(sign(&mut upload(), &scope, &creds, "20150830T123600Z").is_err());
+    }
+
+    #[test]
+    fn test_scope_and_encoding() {
+        assert!("us-east-1".parse::<Scope>().is_err());
+        assert!("/s3".parse::<Scope>().is_err());
+        assert_eq!(canonical_uri("/a%20b", false), "/a%2520b");
+        assert_eq!(canonical_uri("/a%20b", true), "/a%20b");
+        assert_eq!(canonical_uri("", false), "/");
+    }
+}
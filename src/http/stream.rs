@@ -0,0 +1,113 @@
+//! Unbuffered output for `--no-buffer`. The body is copied to stdout chunk
+//! by chunk as it arrives, for event streams and long polls that never
+//! finish, rather than collected and then pretty-printed.
+
+use super::client::HttpClient;
+use super::incoming::{Incoming, Received};
+use super::{empty_body_note, print_headers, print_status};
+use crate::{Opts, Result};
+use colored::*;
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::Request;
+use std::io::{self, IsTerminal, Write};
+
+/// Sends the request, prints the status line and headers as soon as they
+/// arrive, then streams the body. Bodies in a `Content-Encoding` are shown
+/// as received.
+pub async fn fetch(client: &HttpClient, req: Request, opts: &Opts) -> Result<Received> {
+    let incoming = Incoming::send(client, req).await?;
+    print_status(incoming.version(), incoming.status())?;
+    print_headers(incoming.headers(), opts)?;
+    if let Some(encoding) = incoming.headers().get(CONTENT_ENCODING) {
+        meta!(
+            "{} the body is {} and is shown undecoded",
+            "Note:".yellow(),
+            String::from_utf8_lossy(encoding.as_bytes())
+        );
+    }
+    let terminal = io::stdout().is_terminal();
+    let received = copy(incoming, io::stdout(), terminal).await?;
+    if received.bytes == 0 && !opts.empty_ok {
+        meta!("{}", empty_body_note(received.status).dimmed());
+    }
+    Ok(received)
+}
+
+/// Writes each chunk of the body to `out` and flushes it. The bytes are
+/// copied exactly; only on a `terminal` is a missing final newline added,
+/// so that the prompt starts on a line of its own.
+async fn copy(incoming: Incoming, mut out: impl Write, terminal: bool) -> Result<Received> {
+    let mut last = None;
+    let received = incoming
+        .read(|chunk| {
+            out.write_all(chunk)?;
+            out.flush()?;
+            last = chunk.last().copied().or(last);
+            Ok(())
+        })
+        .await?;
+    if terminal && last.is_some_and(|b| b != b'\n') {
+        writeln!(out)?;
+    }
+    Ok(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::redirect::RedirectPolicy;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::oneshot;
+
+    /// Collects what is written and flushed, shared with the test.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_writes_chunks_as_they_arrive() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (finish, finished) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(b"6\r\nfirst\n\r\n").await.unwrap();
+            // The rest of the body waits until the test has seen the
+            // first chunk, which a buffered read never would.
+            finished.await.unwrap();
+            socket.write_all(b"5\r\nlast\n\r\n0\r\n\r\n").await.unwrap();
+        });
+
+        let client =
+            HttpClient::new(reqwest::Client::builder(), None, RedirectPolicy::default()).unwrap();
+        let req = client.get(&url).build().unwrap();
+        let incoming = Incoming::send(&client, req).await.unwrap();
+        let out = Shared::default();
+        let copying = tokio::spawn(copy(incoming, out.clone(), false));
+        for _ in 0..100 {
+            if !out.0.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(*out.0.lock().unwrap(), b"first\n");
+        finish.send(()).unwrap();
+        let received = copying.await.unwrap().unwrap();
+        assert_eq!(received.bytes, 11);
+        assert_eq!(*out.0.lock().unwrap(), b"first\nlast\n");
+    }
+}
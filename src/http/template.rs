@@ -0,0 +1,111 @@
+use super::encode_component;
+use crate::{Error, Result};
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// A `--path name=value` substitution for a `{name}` URL placeholder.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PathParam {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for PathParam {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some((name, value)) if !name.is_empty() => Ok(Self {
+                name: name.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("Expected NAME=VALUE, got {:?}", s).into()),
+        }
+    }
+}
+
+/// `{name}` where the name is an identifier. Other braces, such as JSON in
+/// a query string, are not placeholders.
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// Replaces each `{name}` in `template` with the percent-encoded value of
+/// the matching parameter. Placeholders without a value and values without
+/// a placeholder are both errors, since either is most likely a typo.
+pub fn expand(template: &str, params: &[PathParam]) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut used = Vec::new();
+    let mut last = 0;
+    for caps in PLACEHOLDER.captures_iter(template) {
+        let (whole, name) = (caps.get(0).unwrap(), caps.get(1).unwrap().as_str());
+        // A repeated --path overrides the earlier one.
+        let Some(param) = params.iter().rev().find(|p| p.name == name) else {
+            return Err(format!("No --path value for {{{}}} in {}", name, template).into());
+        };
+        used.push(name);
+        out.push_str(&template[last..whole.start()]);
+        out.push_str(&encode_component(&param.value));
+        last = whole.end();
+    }
+    out.push_str(&template[last..]);
+
+    let unused: Vec<_> = params
+        .iter()
+        .map(|p| p.name.as_str())
+        .filter(|name| !used.contains(name))
+        .collect();
+    if !unused.is_empty() {
+        return Err(format!(
+            "{} has no placeholder for --path {}",
+            template,
+            unused.join(", ")
+        )
+        .into());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(items: &[&str]) -> Vec<PathParam> {
+        items.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand(
+                "https://api.example.com/users/{id}/posts/{slug}?v={id}",
+                &params(&["id=42", "slug=a b/c"])
+            )
+            .unwrap(),
+            "https://api.example.com/users/42/posts/a%20b%2Fc?v=42"
+        );
+        assert_eq!(
+            expand("http://x/{id}", &params(&["id=1", "id=2"])).unwrap(),
+            "http://x/2"
+        );
+        assert_eq!(expand("http://x/a", &[]).unwrap(), "http://x/a");
+        let err = expand("http://x/{id}/{v}", &params(&["v=1"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No --path value for {id} in http://x/{id}/{v}"
+        );
+        assert!(expand("http://x/a", &params(&["id=1"])).is_err());
+        assert!(expand("http://x/{id", &params(&["id=1"])).is_err());
+        assert!("=1".parse::<PathParam>().is_err());
+    }
+
+    #[test]
+    fn test_expand_leaves_other_braces() {
+        let url = r#"http://x/?q={"a":1}"#;
+        assert_eq!(expand(url, &[]).unwrap(), url);
+        assert_eq!(
+            expand(r#"http://x/{id}?q={"a":{}}"#, &params(&["id=7"])).unwrap(),
+            r#"http://x/7?q={"a":{}}"#
+        );
+        assert!(expand("http://x/{id}", &[]).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// Milliseconds spent in each phase of a request. reqwest does not expose
+/// the DNS, connect and TLS phases, so those stay `None` (serialized as
+/// `null`) and only the time to the response head and the total are known.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Timings {
+    pub dns: Option<f64>,
+    pub connect: Option<f64>,
+    pub tls: Option<f64>,
+    pub ttfb: Option<f64>,
+    pub total: Option<f64>,
+}
+
+impl Timings {
+    pub fn new(ttfb: Duration, total: Duration) -> Self {
+        Self {
+            ttfb: Some(millis(ttfb)),
+            total: Some(millis(total)),
+            ..Default::default()
+        }
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_phases_serialize_as_null() {
+        let t = Timings::new(Duration::from_millis(12), Duration::from_millis(30));
+        assert_eq!(
+            serde_json::to_string(&t).unwrap(),
+            r#"{"dns":null,"connect":null,"tls":null,"ttfb":12.0,"total":30.0}"#
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use reqwest::Request;
+use std::fmt::Write;
+
+/// Renders a request the way it goes out over HTTP/1.1: request line,
+/// headers, a blank line and the body.
+///
+/// Only what is on the `Request` can be shown. Headers added while sending,
+/// such as the client's defaults, `Accept` or `Content-Length` for streamed
+/// bodies, are missing; `Host` is derived from the URL.
+pub fn render(req: &Request) -> String {
+    let url = req.url();
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target = format!("{}?{}", target, query);
+    }
+
+    let mut out = format!("{} {} HTTP/1.1\r\n", req.method(), target);
+    if let Some(host) = url.host_str() {
+        match url.port() {
+            Some(port) => write!(out, "Host: {}:{}\r\n", host, port).unwrap(),
+            None => write!(out, "Host: {}\r\n", host).unwrap(),
+        }
+    }
+    for (name, value) in req.headers() {
+        write!(
+            out,
+            "{}: {}\r\n",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        )
+        .unwrap();
+    }
+    let body = req.body().map(|b| b.as_bytes());
+    if let Some(Some(bytes)) = body {
+        write!(out, "content-length: {}\r\n", bytes.len()).unwrap();
+    }
+    out.push_str("\r\n");
+    match body {
+        Some(Some(bytes)) => out.push_str(&String::from_utf8_lossy(bytes)),
+        Some(None) => out.push_str("<streamed body>"),
+        None => {}
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let req = reqwest::Client::new()
+            .post("http://localhost:8080/items?page=2")
+            .header("x-token", "t")
+            .body("{}")
+            .build()
+            .unwrap();
+        assert_eq!(
+            render(&req),
+            "POST /items?page=2 HTTP/1.1\r\n\
+             Host: localhost:8080\r\n\
+             x-token: t\r\n\
+             content-length: 2\r\n\
+             \r\n\
+             {}"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use serde_json::Value;
+
+/// Selects a value by a dotted path such as `data.items.0.id`, where
+/// numeric segments index arrays. `items[0]` is accepted as well, and an
+/// empty path selects the whole document.
+pub fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(value);
+    }
+    path.split('.')
+        .flat_map(|segment| segment.split('[').map(|s| s.trim_end_matches(']')))
+        .filter(|s| !s.is_empty())
+        .try_fold(value, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select() {
+        let doc = json!({"data": {"id": 42, "items": [{"name": "a"}, {"name": "b"}]}});
+        assert_eq!(select(&doc, "data.id"), Some(&json!(42)));
+        assert_eq!(select(&doc, "data.items.1.name"), Some(&json!("b")));
+        assert_eq!(select(&doc, ".data.items[0].name"), Some(&json!("a")));
+        assert_eq!(select(&doc, ""), Some(&doc));
+        assert_eq!(select(&doc, "data.items.9"), None);
+        assert_eq!(select(&doc, "data.id.x"), None);
+    }
+}
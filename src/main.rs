@@ -1,32 +1,369 @@
 // use anyhow::{anyhow, Ok, Result};
+#[macro_use]
+mod output;
+mod config;
 mod error;
 mod http;
+mod json_path;
+mod netrc;
 
+use crate::config::Config;
 use crate::error::{Error, Result};
 use clap::Parser;
-use http::{get::get, post::post, Method};
+use http::assertion::Assertion;
+use http::format::Template;
+use http::redirect::{RedirectPolicy, DEFAULT_MAX_REDIRECTS};
+use http::sigv4::Scope;
+use http::{
+    client::HttpClient, compare::compare, explain::explain, get::get, parse_accept_language,
+    parse_bind, parse_secs, parse_sha256, post::post, probe::probe, Method, OutputFormat,
+};
+use regex::Regex;
 use reqwest::{header, Client};
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
+use terminal_size::{terminal_size, Width};
 
 #[derive(Parser, Debug)]
 #[command(version, author, about, long_about = None)]
 pub struct Opts {
     #[command(subcommand)]
     pub method: Method,
+    /// Re-send the request until the response body contains this text.
+    #[arg(long, global = true, value_name = "TEXT", conflicts_with = "download")]
+    pub repeat_until_body_contains: Option<String>,
+    /// Re-send the request until the response body matches this regex.
+    #[arg(
+        long,
+        global = true,
+        value_name = "REGEX",
+        conflicts_with_all = ["repeat_until_body_contains", "download"]
+    )]
+    pub repeat_until_body_matches: Option<Regex>,
+    /// Seconds to wait between repeated attempts.
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECS",
+        default_value = "1",
+        value_parser = parse_secs
+    )]
+    pub interval: Duration,
+    /// Give up repeating after this many attempts.
+    #[arg(long, global = true, value_name = "N", default_value_t = 10)]
+    pub max_attempts: u32,
+    /// Ask for these languages, e.g. "en-US, fr;q=0.8".
+    #[arg(
+        long,
+        global = true,
+        value_name = "LANGUAGES",
+        value_parser = parse_accept_language
+    )]
+    pub accept_language: Option<String>,
+    /// Send requests from this local IP address, e.g. to pick a network
+    /// interface on a multi-homed host.
+    #[arg(long, global = true, value_name = "IP", value_parser = parse_bind)]
+    pub bind: Option<IpAddr>,
+    /// Cap the number of requests in flight at once. Unlimited by default.
+    #[arg(long, global = true, value_name = "N")]
+    pub max_connections: Option<NonZeroUsize>,
+    /// Send an X-Request-Id header, generating a UUID unless one is given
+    /// as --request-id=ID.
+    #[arg(long, global = true, value_name = "ID", num_args = 0..=1, require_equals = true)]
+    pub request_id: Option<Option<String>>,
+    /// Send this X-Request-Id; the same as --request-id=ID.
+    #[arg(long, global = true, value_name = "ID", conflicts_with = "request_id")]
+    pub request_id_value: Option<String>,
+    /// Save the response body to a file instead of printing it. The bytes
+    /// are streamed to disk as received, without undoing any
+    /// Content-Encoding, so options that read the body do not apply.
+    #[arg(short, long, global = true)]
+    pub download: bool,
+    /// The file to download to. Defaults to a name derived from the URL.
+    #[arg(short, long, global = true, value_name = "FILE", requires = "download")]
+    pub output: Option<PathBuf>,
+    /// Print the SHA-256 of the downloaded file, or check it against a
+    /// digest given as --sha256=DIGEST and fail on a mismatch.
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIGEST",
+        num_args = 0..=1,
+        require_equals = true,
+        requires = "download",
+        value_parser = parse_sha256
+    )]
+    pub sha256: Option<Option<String>>,
+    /// Check the downloaded file against this SHA-256; the same as
+    /// --sha256=DIGEST.
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIGEST",
+        conflicts_with = "sha256",
+        requires = "download",
+        value_parser = parse_sha256
+    )]
+    pub expect_sha256: Option<String>,
+    /// Do not keep this response for a later --body @last or $LAST. Others
+    /// are saved to httpie/last-response in the cache directory until the
+    /// next one replaces them; delete that file to clear it.
+    #[arg(long, global = true)]
+    pub no_save_last: bool,
+    /// Expand $NAME and ${NAME} in the URL from the environment. Quote the
+    /// URL so the shell leaves them alone.
+    #[arg(long, global = true)]
+    pub expand_env_in_url: bool,
+    /// Expand unset variables to nothing instead of failing.
+    #[arg(long, global = true, requires = "expand_env_in_url")]
+    pub allow_unset_env: bool,
+    /// Describe the request in plain English before sending it.
+    #[arg(long, global = true)]
+    pub explain: bool,
+    /// Describe the request in plain English without sending it.
+    #[arg(long, global = true)]
+    pub explain_only: bool,
+    /// Print the status line, headers and notes to stderr so that stdout
+    /// carries only the response body.
+    #[arg(long, global = true)]
+    pub meta_to_stderr: bool,
+    /// How to print the response. With json, stdout carries only the JSON
+    /// document and notes go to stderr, as with --meta-to-stderr.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+    /// Print the request and response as an OpenAPI 3 example fragment
+    /// instead of the usual output.
+    #[arg(long, global = true, conflicts_with = "download")]
+    pub as_openapi_example: bool,
+    /// Print only this template instead of the response, filling in
+    /// %{status}, %{time} and %{ttfb} (ms), %{size} (bytes), %{url},
+    /// %{version} and %{header:NAME}.
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "output-template",
+        value_name = "TEMPLATE",
+        conflicts_with = "as_openapi_example"
+    )]
+    pub format: Option<Template>,
+    /// Ask for a compressed response and report how much the encoding
+    /// saved.
+    #[arg(long, global = true)]
+    pub show_compression: bool,
+    /// Print only the number of elements in a JSON array body, or of keys
+    /// in an object.
+    #[arg(long, global = true, conflicts_with_all = ["format", "as_openapi_example"])]
+    pub count: bool,
+    /// Print a SHA-256 fingerprint of the request, stable across runs, to
+    /// correlate identical requests. JSON output carries it as a
+    /// `fingerprint` field.
+    #[arg(long, global = true)]
+    pub fingerprint: bool,
+    /// Report how long the request took.
+    #[arg(long, global = true)]
+    pub timing: bool,
+    /// Exit with an error unless the response body is empty.
+    #[arg(long, global = true)]
+    pub expect_empty: bool,
+    /// Exit with an error and print a diff unless the body matches this
+    /// fixture. JSON is compared with sorted keys, ignoring formatting.
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "download")]
+    pub expect_body_file: Option<PathBuf>,
+    /// Write the response body to the --expect-body-file fixture instead of
+    /// comparing against it.
+    #[arg(long, global = true, requires = "expect_body_file")]
+    pub update_fixture: bool,
+    /// Print nothing at all for an empty body instead of an "(empty body)"
+    /// or "(no content)" note.
+    #[arg(long, global = true)]
+    pub empty_ok: bool,
+    /// Follow at most this many redirects [default: 10].
+    #[arg(long, global = true, value_name = "N")]
+    pub max_redirects: Option<usize>,
+    /// Replay the original method and body when following 301, 302 and 303
+    /// redirects, instead of switching to GET as browsers do.
+    #[arg(long, global = true)]
+    pub keep_method_on_redirect: bool,
+    /// Refuse to follow a redirect from https to plain http.
+    #[arg(long, global = true)]
+    pub no_http_downgrade: bool,
+    /// Keep Authorization, cookies and other credentials when a redirect
+    /// leaves the original host. They are dropped by default.
+    #[arg(long, global = true)]
+    pub allow_cross_origin_auth: bool,
+    /// Print each redirect hop as it is followed.
+    #[arg(long, global = true, overrides_with = "quiet_redirects")]
+    pub trace: bool,
+    /// Print only the final response of a redirect chain. This is the
+    /// default; use it to override a --trace set elsewhere, such as in an
+    /// alias.
+    #[arg(long, global = true, overrides_with = "trace")]
+    pub quiet_redirects: bool,
+    /// Wrap long lines at this many columns; 0 turns wrapping off.
+    /// Defaults to the terminal width.
+    #[arg(long, global = true, value_name = "COLS")]
+    pub wrap: Option<usize>,
+    /// Print response header values exactly as received, unquoted and
+    /// unwrapped, one line per value.
+    #[arg(long, global = true)]
+    pub raw_headers: bool,
+    /// Decode JWTs found in request and response headers and print their
+    /// claims. Signatures are not verified.
+    #[arg(long, global = true)]
+    pub decode_jwt: bool,
+    /// Show the body as gRPC-web frames and raw protobuf fields. On by
+    /// default for application/grpc-web responses.
+    #[arg(long, global = true)]
+    pub grpc_web: bool,
+    /// Ask for HTTP trailers and print them after the body.
+    #[arg(long, global = true)]
+    pub trailers: bool,
+    /// Print the equivalent curl command instead of sending the request.
+    #[arg(long, global = true)]
+    pub curl: bool,
+    /// Copy the equivalent curl command to the clipboard instead of
+    /// sending the request.
+    #[arg(long, global = true, conflicts_with = "curl")]
+    pub dry_run_curl_to_clipboard: bool,
+    /// Show credentials in the curl command instead of `<redacted>`.
+    #[arg(long, global = true)]
+    pub curl_unredacted: bool,
+    /// Print the body as it arrives, flushing each chunk, for event streams
+    /// and consumers reading from a pipe. The body is shown as sent, without
+    /// highlighting, and is not kept for --body @last.
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = [
+            "download", "tee", "format", "count", "as_openapi_example", "grep", "depth",
+            "grpc_web", "show_compression", "assert", "expect_body_file",
+            "repeat_until_body_contains", "repeat_until_body_matches",
+        ]
+    )]
+    pub no_buffer: bool,
+    /// Print the request as it goes over the wire before sending it.
+    #[arg(long, global = true)]
+    pub print_wire: bool,
+    /// Send basic auth credentials for the host from ~/.netrc, if any.
+    #[arg(long, global = true)]
+    pub netrc: bool,
+    /// Like --netrc, but fail when the host has no netrc entry.
+    #[arg(long, global = true)]
+    pub netrc_required: bool,
+    /// Read netrc credentials from this file instead of ~/.netrc.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub netrc_file: Option<PathBuf>,
+    /// Read per-host headers from this file instead of
+    /// httpie/config.toml in the user config directory.
+    #[arg(long = "config", global = true, value_name = "FILE")]
+    pub config_path: Option<PathBuf>,
+    /// The loaded config file, filled in after parsing.
+    #[arg(skip)]
+    pub config: Config,
+    /// Sign the request with AWS Signature Version 4, e.g. us-east-1/s3.
+    #[arg(long, global = true, value_name = "REGION/SERVICE", conflicts_with_all = ["netrc", "netrc_required"])]
+    pub aws_sigv4: Option<Scope>,
+    /// The access key ID for --aws-sigv4.
+    #[arg(long, global = true, env = "AWS_ACCESS_KEY_ID", hide_env_values = true)]
+    pub aws_access_key_id: Option<String>,
+    /// The secret access key for --aws-sigv4.
+    #[arg(
+        long,
+        global = true,
+        env = "AWS_SECRET_ACCESS_KEY",
+        hide_env_values = true
+    )]
+    pub aws_secret_access_key: Option<String>,
+    /// The session token for --aws-sigv4, when using temporary credentials.
+    #[arg(long, global = true, env = "AWS_SESSION_TOKEN", hide_env_values = true)]
+    pub aws_session_token: Option<String>,
+    /// Collapse JSON objects and arrays nested deeper than this many levels
+    /// into {...} and [...].
+    #[arg(long, global = true, value_name = "N")]
+    pub depth: Option<usize>,
+    /// Print only the body lines matching this regex.
+    #[arg(long, global = true, value_name = "REGEX")]
+    pub grep: Option<Regex>,
+    /// Lines of context to print around each --grep match.
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "grep"
+    )]
+    pub grep_context: usize,
+    /// Check that the JSON value at a dotted path equals the expected
+    /// value, e.g. data.id=42. May be repeated.
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH=VALUE",
+        conflicts_with = "download"
+    )]
+    pub assert: Vec<Assertion>,
+    /// Send a HEAD request first so timings exclude connection setup.
+    #[arg(long, global = true)]
+    pub warmup: bool,
+    /// Also write the raw response body to this file while printing it.
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "download")]
+    pub tee: Option<PathBuf>,
+    /// Record Prometheus metrics for the request (duration, status and body
+    /// size) in this file, e.g. for node_exporter's textfile collector.
+    /// Series from earlier runs are kept, and replaced for the same URL.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub metrics_file: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let opts: Opts = Opts::parse();
+    if http::curl::hold() {
+        return Ok(());
+    }
+    let mut opts: Opts = Opts::parse();
+    // The space-separated spellings are folded into the optional-value flags.
+    if let Some(id) = opts.request_id_value.take() {
+        opts.request_id = Some(Some(id));
+    }
+    if let Some(digest) = opts.expect_sha256.take() {
+        opts.sha256 = Some(Some(digest));
+    }
+    opts.config = Config::load(opts.config_path.as_deref())?;
+    output::set_meta_to_stderr(opts.meta_to_stderr || opts.output_format == OutputFormat::Json);
+    output::set_wrap_width(
+        opts.wrap
+            .unwrap_or_else(|| terminal_size().map_or(0, |(Width(w), _)| w as usize)),
+    );
+    if opts.explain || opts.explain_only {
+        meta!("{}\n", explain(&opts));
+        if opts.explain_only {
+            return Ok(());
+        }
+    }
 
     // let client = Client::new();
     let mut headers = header::HeaderMap::new();
     headers.insert("X-POWERED-BY", "Rust".parse()?);
     headers.insert(header::USER_AGENT, "Rust Httpie".parse()?);
-    let client = Client::builder().default_headers(headers).build()?;
-
-    let result = match opts.method {
-        Method::Get(ref args) => get(client, args).await?,
-        Method::Post(ref args) => post(client, args).await?,
+    let redirects = RedirectPolicy {
+        max: opts.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS),
+        keep_method: opts.keep_method_on_redirect,
+        refuse_downgrade: opts.no_http_downgrade,
+        allow_cross_origin_auth: opts.allow_cross_origin_auth,
+        trace: opts.trace,
     };
-    Ok(result)
+    let builder = Client::builder()
+        .default_headers(headers)
+        .cookie_store(true)
+        .local_address(opts.bind);
+    let client = HttpClient::new(builder, opts.max_connections, redirects)?;
+
+    match opts.method {
+        Method::Get(ref args) => get(&client, args, &opts).await,
+        Method::Post(ref args) => post(&client, args, &opts).await,
+        Method::Probe(ref args) => probe(&client, args).await,
+        Method::Compare(ref args) => compare(&client, args).await,
+    }
 }
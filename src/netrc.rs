@@ -0,0 +1,108 @@
+use crate::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Entry {
+    /// `None` for the `default` entry, which matches any host.
+    pub machine: Option<String>,
+    pub login: Option<String>,
+    pub password: Option<String>,
+}
+
+/// `~/.netrc`, where curl looks by default.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".netrc"))
+}
+
+/// Finds the credentials for `host` in the netrc file at `path`.
+pub fn lookup(path: &Path, host: &str) -> Result<Option<Entry>> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(find(&parse(&data), host).cloned())
+}
+
+/// Picks the entry for `host`, falling back to the `default` entry.
+pub fn find<'a>(entries: &'a [Entry], host: &str) -> Option<&'a Entry> {
+    entries
+        .iter()
+        .find(|e| e.machine.as_deref() == Some(host))
+        .or_else(|| entries.iter().find(|e| e.machine.is_none()))
+}
+
+/// Parses netrc tokens. Unknown tokens are skipped, as are `macdef`
+/// bodies, which run until the next blank line.
+pub fn parse(data: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut lines = data.lines();
+    while let Some(line) = lines.next() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        while let Some(token) = tokens.next() {
+            match token {
+                "machine" => entries.push(Entry {
+                    machine: tokens.next().map(String::from),
+                    ..Default::default()
+                }),
+                "default" => entries.push(Entry::default()),
+                "login" | "password" | "account" => {
+                    let value = tokens.next().map(String::from);
+                    if let Some(entry) = entries.last_mut() {
+                        match token {
+                            "login" => entry.login = value,
+                            "password" => entry.password = value,
+                            _ => {}
+                        }
+                    }
+                }
+                "macdef" => {
+                    for line in lines.by_ref() {
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                    }
+                    break;
+                }
+                _ => {}
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_find() {
+        let entries = parse(
+            "# work\n\
+             machine api.example.com login me password s3cret\n\
+             macdef init\n\
+             cd /tmp\n\
+             \n\
+             machine other.example.com\n  login you\n  password pw\n\
+             default login anon password guest\n",
+        );
+        assert_eq!(entries.len(), 3);
+
+        let e = find(&entries, "api.example.com").unwrap();
+        assert_eq!(e.login.as_deref(), Some("me"));
+        assert_eq!(e.password.as_deref(), Some("s3cret"));
+        assert_eq!(
+            find(&entries, "other.example.com")
+                .unwrap()
+                .login
+                .as_deref(),
+            Some("you")
+        );
+        assert_eq!(
+            find(&entries, "unknown.io").unwrap().login.as_deref(),
+            Some("anon")
+        );
+        assert_eq!(find(&parse("machine a login b"), "c"), None);
+    }
+}
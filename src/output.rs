@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static META_TO_STDERR: AtomicBool = AtomicBool::new(false);
+static WRAP_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Routes metadata (status line, headers, progress notes) to stderr, leaving
+/// stdout with nothing but the response body.
+pub fn set_meta_to_stderr(enabled: bool) {
+    META_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
+
+pub fn meta_to_stderr() -> bool {
+    META_TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Wraps printed lines at `width` columns; 0 disables wrapping.
+pub fn set_wrap_width(width: usize) {
+    WRAP_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// Wraps each line of `s` at the configured width.
+pub fn wrap(s: &str) -> Cow<'_, str> {
+    match WRAP_WIDTH.load(Ordering::Relaxed) {
+        0 => Cow::Borrowed(s),
+        width => Cow::Owned(
+            s.split('\n')
+                .map(|line| wrap_line(line, width))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    }
+}
+
+/// Breaks a line every `width` visible columns, indenting continuations two
+/// columns past the line's own indentation. ANSI escape sequences take no
+/// columns, and the terminal keeps their colors across the break.
+fn wrap_line(line: &str, width: usize) -> String {
+    let mut visible = strip_escapes(line);
+    let lead = visible.by_ref().take_while(|c| *c == ' ').count();
+    let indent = match lead + 2 {
+        n if n < width => " ".repeat(n),
+        _ => String::new(),
+    };
+
+    let mut out = String::with_capacity(line.len());
+    let mut col = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        if col == width {
+            out.push('\n');
+            out.push_str(&indent);
+            col = indent.len();
+        }
+        out.push(c);
+        col += 1;
+    }
+    out
+}
+
+fn strip_escapes(s: &str) -> impl Iterator<Item = char> + '_ {
+    let mut in_escape = false;
+    s.chars().filter(move |&c| {
+        if in_escape {
+            in_escape = !c.is_ascii_alphabetic();
+            return false;
+        }
+        in_escape = c == '\x1b';
+        !in_escape
+    })
+}
+
+/// Like `println!`, but for metadata rather than the response body.
+macro_rules! meta {
+    ($($arg:tt)*) => {
+        if $crate::output::meta_to_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_line() {
+        assert_eq!(wrap_line("abcdefgh", 4), "abcd\n  ef\n  gh");
+        assert_eq!(wrap_line("  abcdefgh", 6), "  abcd\n    ef\n    gh");
+        assert_eq!(wrap_line("abc", 4), "abc");
+        assert_eq!(
+            wrap_line("\x1b[31mabcdef\x1b[0m", 4),
+            "\x1b[31mabcd\n  ef\x1b[0m"
+        );
+    }
+}